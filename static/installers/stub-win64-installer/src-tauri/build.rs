@@ -1,3 +1,54 @@
+use sha2::{Digest, Sha256};
+use std::{env, fs};
+
 fn main() {
+    embed_offline_installer();
     tauri_build::build()
 }
+
+/// With the `offline` feature, embeds the installer named by
+/// `FLOORP_OFFLINE_INSTALLER` after checking it against the published
+/// digest in `FLOORP_OFFLINE_INSTALLER_SHA256`.
+fn embed_offline_installer() {
+    if env::var_os("CARGO_FEATURE_OFFLINE").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-env-changed=FLOORP_OFFLINE_INSTALLER");
+    println!("cargo:rerun-if-env-changed=FLOORP_OFFLINE_INSTALLER_SHA256");
+
+    let path = env::var("FLOORP_OFFLINE_INSTALLER")
+        .expect("the offline feature requires FLOORP_OFFLINE_INSTALLER");
+    let path = fs::canonicalize(&path)
+        .unwrap_or_else(|e| panic!("cannot find offline installer {}: {}", path, e));
+    let expected = env::var("FLOORP_OFFLINE_INSTALLER_SHA256")
+        .expect("the offline feature requires FLOORP_OFFLINE_INSTALLER_SHA256")
+        .trim()
+        .to_ascii_lowercase();
+
+    let bytes = fs::read(&path)
+        .unwrap_or_else(|e| panic!("cannot read offline installer {}: {}", path.display(), e));
+    let actual = hex::encode(Sha256::digest(&bytes));
+    if actual != expected {
+        panic!(
+            "offline installer {} has SHA-256 {}, expected {}",
+            path.display(),
+            actual,
+            expected
+        );
+    }
+
+    let name = path
+        .file_name()
+        .expect("offline installer path has no file name")
+        .to_string_lossy();
+    println!("cargo:rerun-if-changed={}", path.display());
+    println!(
+        "cargo:rustc-env=FLOORP_OFFLINE_INSTALLER_PATH={}",
+        path.display()
+    );
+    println!("cargo:rustc-env=FLOORP_OFFLINE_INSTALLER_NAME={}", name);
+    println!(
+        "cargo:rustc-env=FLOORP_OFFLINE_INSTALLER_SHA256={}",
+        expected
+    );
+}
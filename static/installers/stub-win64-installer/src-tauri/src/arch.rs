@@ -0,0 +1,147 @@
+//! Machine architecture detection and per-architecture CPU requirements.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A CPU architecture Floorp publishes Windows builds for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arch {
+    X86_64,
+    Aarch64,
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+        })
+    }
+}
+
+impl Arch {
+    /// Builds that run on this machine, best first. ARM64 Windows runs x64
+    /// binaries under emulation, so the x64 build is its fallback.
+    pub fn installable(self) -> &'static [Arch] {
+        match self {
+            Arch::X86_64 => &[Arch::X86_64],
+            Arch::Aarch64 => &[Arch::Aarch64, Arch::X86_64],
+        }
+    }
+
+    /// CPU features the Floorp build for this architecture requires.
+    pub fn required_features(self) -> &'static [&'static str] {
+        match self {
+            Arch::X86_64 => &["sse4.1"],
+            Arch::Aarch64 => &["neon"],
+        }
+    }
+}
+
+/// The native architecture of the machine, which differs from the stub's
+/// own when it runs as an emulated x64 process on ARM64.
+#[cfg(windows)]
+pub fn native_arch() -> Arch {
+    use windows::Win32::System::SystemInformation::{IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_ARM64};
+    use windows::Win32::System::Threading::{GetCurrentProcess, IsWow64Process2};
+
+    let mut process_machine = IMAGE_FILE_MACHINE::default();
+    let mut native_machine = IMAGE_FILE_MACHINE::default();
+    let detected = unsafe {
+        IsWow64Process2(
+            GetCurrentProcess(),
+            &mut process_machine,
+            Some(&mut native_machine),
+        )
+    };
+
+    match detected {
+        Ok(()) if native_machine == IMAGE_FILE_MACHINE_ARM64 => Arch::Aarch64,
+        Ok(()) => Arch::X86_64,
+        Err(e) => {
+            println!(
+                "[WARN] IsWow64Process2 failed, assuming build architecture: {}",
+                e
+            );
+            build_arch()
+        }
+    }
+}
+
+/// The native architecture of the machine.
+#[cfg(not(windows))]
+pub fn native_arch() -> Arch {
+    build_arch()
+}
+
+fn build_arch() -> Arch {
+    if cfg!(target_arch = "aarch64") {
+        Arch::Aarch64
+    } else {
+        Arch::X86_64
+    }
+}
+
+/// Whether this process can observe `feature`, and if so whether the CPU
+/// has it. An x64 process cannot inspect ARM64 features and vice versa.
+pub fn detect_feature(feature: &str) -> Option<bool> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        match feature {
+            "sse4.1" => Some(is_x86_feature_detected!("sse4.1")),
+            _ => None,
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        match feature {
+            "neon" => Some(std::arch::is_aarch64_feature_detected!("neon")),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        let _ = feature;
+        None
+    }
+}
+
+/// Required features of `arch` that `detect` reports as missing. Features
+/// that cannot be detected from this process are assumed present.
+pub fn missing_features(arch: Arch, detect: impl Fn(&str) -> Option<bool>) -> Vec<&'static str> {
+    arch.required_features()
+        .iter()
+        .copied()
+        .filter(|feature| match detect(feature) {
+            Some(present) => {
+                println!("[INFO] CPU feature {}: {}", feature, present);
+                !present
+            }
+            None => {
+                println!(
+                    "[INFO] CPU feature {} cannot be checked, assuming support",
+                    feature
+                );
+                false
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_only_detectably_missing_features() {
+        assert_eq!(
+            missing_features(Arch::X86_64, |_| Some(false)),
+            vec!["sse4.1"]
+        );
+        assert!(missing_features(Arch::X86_64, |_| Some(true)).is_empty());
+        assert!(missing_features(Arch::Aarch64, |_| None).is_empty());
+    }
+}
@@ -0,0 +1,352 @@
+//! Verified installers kept for later runs.
+//!
+//! After an installer passed its checks it is copied to
+//! `%LOCALAPPDATA%\Floorp-Installer\cache` as `<version>-<sha256>.exe`, so
+//! retrying a failed install or repairing one does not download it again.
+//! A cached file is hashed again whenever it is used, and the least recently
+//! used entries are removed once the cache outgrows its size limit.
+
+use crate::checksum::parse_sha256;
+use crate::workspace::create_private_dir;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const ENTRY_EXTENSION: &str = "exe";
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CacheConfig {
+    /// Whether verified installers are kept for later runs.
+    pub enabled: bool,
+    /// Size the cache is pruned to, least recently used entries first.
+    pub max_bytes: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: true,
+            max_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+pub struct InstallerCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl InstallerCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        InstallerCache { dir, max_bytes }
+    }
+
+    /// The cache under `%LOCALAPPDATA%`, or `None` if it is disabled.
+    pub fn open(config: &CacheConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let local_appdata = env::var("LOCALAPPDATA").ok()?;
+        let dir = PathBuf::from(local_appdata)
+            .join("Floorp-Installer")
+            .join("cache");
+        Some(InstallerCache::new(dir, config.max_bytes))
+    }
+
+    /// Creates the cache directory private to the user, so that no other
+    /// account can plant an entry in it.
+    fn create_dir(&self) -> io::Result<()> {
+        if let Some(parent) = self.dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match create_private_dir(&self.dir) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && self.dir.is_dir() => Ok(()),
+            result => result,
+        }
+    }
+
+    fn entry_path(&self, version: &str, sha256: &str) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{}.{}",
+            file_safe(version),
+            sha256,
+            ENTRY_EXTENSION
+        ))
+    }
+
+    /// Copies the cached installer for `version` to `dest` and returns its
+    /// digest. Without an `expected_sha256` the newest entry for the version
+    /// is used. An entry whose contents no longer match its digest is
+    /// removed, and `None` returned as for a miss.
+    pub async fn fetch(
+        &self,
+        version: &str,
+        expected_sha256: Option<&str>,
+        dest: &Path,
+    ) -> Option<String> {
+        let (entry, sha256) = match expected_sha256 {
+            Some(sha256) => (self.entry_path(version, sha256), sha256.to_string()),
+            None => self.newest_entry(version)?,
+        };
+        if !entry.is_file() {
+            return None;
+        }
+
+        match copy_hashed(&entry, dest).await {
+            Ok(actual) if actual == sha256 => {
+                touch(&entry);
+                Some(actual)
+            }
+            Ok(actual) => {
+                println!(
+                    "[WARN] Discarding corrupt cache entry {}: digest is {}",
+                    entry.display(),
+                    actual
+                );
+                let _ = fs::remove_file(&entry);
+                let _ = fs::remove_file(dest);
+                None
+            }
+            Err(e) => {
+                println!("[WARN] Could not read cache entry: {}", e);
+                let _ = fs::remove_file(dest);
+                None
+            }
+        }
+    }
+
+    fn newest_entry(&self, version: &str) -> Option<(PathBuf, String)> {
+        let prefix = format!("{}-", file_safe(version));
+        self.entries()
+            .into_iter()
+            .filter_map(|entry| {
+                let name = entry.path.file_stem()?.to_str()?;
+                let sha256 = parse_sha256(name.strip_prefix(&prefix)?)?;
+                Some((entry, sha256))
+            })
+            .max_by_key(|(entry, _)| entry.modified)
+            .map(|(entry, sha256)| (entry.path, sha256))
+    }
+
+    /// Adds the verified installer at `source`, then prunes the cache.
+    pub async fn store(&self, version: &str, sha256: &str, source: &Path) -> Result<(), String> {
+        let entry = self.entry_path(version, sha256);
+        if entry.is_file() {
+            touch(&entry);
+            return Ok(());
+        }
+
+        self.create_dir()
+            .map_err(|e| format!("Failed to create {}: {}", self.dir.display(), e))?;
+        let partial = entry.with_extension("part");
+        let copied = copy_hashed(source, &partial).await;
+        match copied {
+            Ok(actual) if actual == sha256 => {}
+            Ok(actual) => {
+                let _ = fs::remove_file(&partial);
+                return Err(format!("digest changed while caching: {}", actual));
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&partial);
+                return Err(e);
+            }
+        }
+        fs::rename(&partial, &entry).map_err(|e| format!("File rename error: {}", e))?;
+        println!("[INFO] Cached installer as {}", entry.display());
+
+        self.prune();
+        Ok(())
+    }
+
+    /// Removes the least recently used entries until the rest fit into the
+    /// size limit.
+    pub fn prune(&self) {
+        let mut entries = self.entries();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.modified));
+
+        let mut total = 0u64;
+        for entry in entries {
+            total += entry.len;
+            if total > self.max_bytes {
+                println!("[INFO] Pruning cached installer {}", entry.path.display());
+                let _ = fs::remove_file(&entry.path);
+            }
+        }
+    }
+
+    fn entries(&self) -> Vec<CacheEntry> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        dir.flatten()
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == ENTRY_EXTENSION)
+            })
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+                Some(CacheEntry {
+                    path: entry.path(),
+                    len: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                })
+            })
+            .collect()
+    }
+}
+
+struct CacheEntry {
+    path: PathBuf,
+    len: u64,
+    modified: SystemTime,
+}
+
+/// Keeps letters, digits, dots and dashes of a version, so any tag makes a
+/// valid file name.
+fn file_safe(version: &str) -> String {
+    version
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Marks `entry` as used, for pruning.
+fn touch(entry: &Path) {
+    if let Ok(file) = fs::File::options().write(true).open(entry) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Copies `source` to `dest` and returns the SHA-256 of what was written.
+async fn copy_hashed(source: &Path, dest: &Path) -> Result<String, String> {
+    let mut input = File::open(source)
+        .await
+        .map_err(|e| format!("File open error: {}", e))?;
+    let mut output = File::create(dest)
+        .await
+        .map_err(|e| format!("File creation error: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+
+    loop {
+        let n = input
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("File read error: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        output
+            .write_all(&buf[..n])
+            .await
+            .map_err(|e| format!("File write error: {}", e))?;
+    }
+
+    output
+        .flush()
+        .await
+        .map_err(|e| format!("File write error: {}", e))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn digest(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    fn installer(dir: &Path, name: &str, data: &[u8]) -> (PathBuf, String) {
+        let path = dir.join(name);
+        fs::write(&path, data).unwrap();
+        (path, digest(data))
+    }
+
+    #[tokio::test]
+    async fn reuses_a_stored_installer_after_checking_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = InstallerCache::new(dir.path().join("cache"), u64::MAX);
+        let (source, sha256) = installer(dir.path(), "setup.exe", b"installer v1");
+        let dest = dir.path().join("copy.exe");
+
+        assert_eq!(cache.fetch("v12.0.0", Some(&sha256), &dest).await, None);
+        cache.store("v12.0.0", &sha256, &source).await.unwrap();
+
+        assert_eq!(
+            cache.fetch("v12.0.0", Some(&sha256), &dest).await,
+            Some(sha256.clone())
+        );
+        assert_eq!(fs::read(&dest).unwrap(), b"installer v1");
+        assert_eq!(cache.fetch("v12.0.0", None, &dest).await, Some(sha256));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&cache.dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        assert_eq!(cache.fetch("v12.0.1", None, &dest).await, None);
+    }
+
+    #[tokio::test]
+    async fn discards_entries_that_no_longer_match_their_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = InstallerCache::new(dir.path().join("cache"), u64::MAX);
+        let (source, sha256) = installer(dir.path(), "setup.exe", b"installer v1");
+        cache.store("v12.0.0", &sha256, &source).await.unwrap();
+
+        let entry = cache.entry_path("v12.0.0", &sha256);
+        fs::write(&entry, b"tampered").unwrap();
+        let dest = dir.path().join("copy.exe");
+
+        assert_eq!(cache.fetch("v12.0.0", Some(&sha256), &dest).await, None);
+        assert!(!entry.exists());
+        assert!(!dest.exists());
+    }
+
+    #[tokio::test]
+    async fn prunes_least_recently_used_entries_over_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = InstallerCache::new(dir.path().join("cache"), 25);
+        let versions = ["v1", "v2", "v3"];
+        let mut digests = Vec::new();
+
+        for (i, version) in versions.iter().enumerate() {
+            let (source, sha256) = installer(
+                dir.path(),
+                "setup.exe",
+                format!("installer {}", i).as_bytes(),
+            );
+            cache.store(version, &sha256, &source).await.unwrap();
+            digests.push(sha256);
+            // Modification times must differ for the order to be known.
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        // Each entry holds 11 bytes, so only the two newest fit.
+        let cached: Vec<bool> = versions
+            .iter()
+            .zip(&digests)
+            .map(|(version, sha256)| cache.entry_path(version, sha256).exists())
+            .collect();
+        assert_eq!(cached, vec![false, true, true]);
+        assert_eq!(file_safe("refs/tags/v1"), "refs_tags_v1");
+    }
+}
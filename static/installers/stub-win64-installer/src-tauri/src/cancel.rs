@@ -0,0 +1,192 @@
+//! Cancelling an install that is under way.
+//!
+//! Every run of the install command gets a [`CancelToken`] from the
+//! [`InstallControl`] kept in the Tauri state. Long-running steps race the
+//! token, so a cancel stops whatever is in progress at its next await point.
+//! Once the NSIS installer runs, stopping it leaves a half-installed browser
+//! behind, so that needs the user's confirmation. An installer started
+//! elevated cannot be stopped from here at all, so cancel is refused then.
+
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Error key returned by an install that was cancelled.
+pub const CANCELLED_ERROR: &str = "rust.errors.cancelled";
+
+/// The install was cancelled before the step finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("cancelled")
+    }
+}
+
+#[derive(Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Shared flag telling the steps of one install to stop.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    state: Arc<TokenState>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once the token is cancelled.
+    pub async fn cancelled(&self) {
+        let notified = self.state.notify.notified();
+        tokio::pin!(notified);
+        // Registered before the check, so a cancel in between is not missed.
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Runs `step` unless the token is cancelled first, in which case the
+    /// step is dropped where it stands.
+    pub async fn guard<F: Future>(&self, step: F) -> Result<F::Output, Cancelled> {
+        if self.is_cancelled() {
+            return Err(Cancelled);
+        }
+        tokio::select! {
+            biased;
+            _ = self.cancelled() => Err(Cancelled),
+            output = step => Ok(output),
+        }
+    }
+}
+
+/// The step a running install has reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstallPhase {
+    /// Resolving, downloading or verifying the installer.
+    Preparing,
+    /// The NSIS installer is running.
+    RunningInstaller,
+    /// The NSIS installer runs elevated, out of reach of this process.
+    RunningElevated,
+}
+
+/// What a cancel request did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CancelOutcome {
+    Cancelled,
+    /// The installer is already running; ask again with confirmation.
+    ConfirmationRequired,
+    /// The installer runs elevated and cannot be stopped; nothing was done.
+    NotCancellable,
+    /// No install is running.
+    Idle,
+}
+
+#[derive(Default)]
+pub struct InstallControl {
+    current: Mutex<Option<(CancelToken, InstallPhase)>>,
+}
+
+impl InstallControl {
+    /// Registers a new install and returns its token.
+    pub fn start(&self) -> CancelToken {
+        let token = CancelToken::default();
+        *self.current.lock().unwrap() = Some((token.clone(), InstallPhase::Preparing));
+        token
+    }
+
+    pub fn enter(&self, phase: InstallPhase) {
+        if let Some((_, current)) = self.current.lock().unwrap().as_mut() {
+            *current = phase;
+        }
+    }
+
+    pub fn finish(&self) {
+        *self.current.lock().unwrap() = None;
+    }
+
+    /// Cancels the running install. Once the installer runs, this only
+    /// happens when `confirmed` is set, and not at all when it runs
+    /// elevated.
+    pub fn cancel(&self, confirmed: bool) -> CancelOutcome {
+        match self.current.lock().unwrap().as_ref() {
+            None => CancelOutcome::Idle,
+            Some((_, InstallPhase::RunningElevated)) => CancelOutcome::NotCancellable,
+            Some((_, InstallPhase::RunningInstaller)) if !confirmed => {
+                CancelOutcome::ConfirmationRequired
+            }
+            Some((token, _)) => {
+                token.cancel();
+                CancelOutcome::Cancelled
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    #[tokio::test]
+    async fn cancel_stops_a_running_step() {
+        let token = CancelToken::default();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let result = token.guard(sleep(Duration::from_secs(30))).await;
+
+        assert_eq!(result, Err(Cancelled));
+        assert_eq!(token.guard(async { 1 }).await, Err(Cancelled));
+        assert_eq!(CancelToken::default().guard(async { 1 }).await, Ok(1));
+    }
+
+    #[test]
+    fn running_installer_needs_confirmation() {
+        let control = InstallControl::default();
+        assert_eq!(control.cancel(false), CancelOutcome::Idle);
+
+        let token = control.start();
+        control.enter(InstallPhase::RunningInstaller);
+        assert_eq!(control.cancel(false), CancelOutcome::ConfirmationRequired);
+        assert!(!token.is_cancelled());
+
+        assert_eq!(control.cancel(true), CancelOutcome::Cancelled);
+        assert!(token.is_cancelled());
+
+        control.finish();
+        assert_eq!(control.cancel(true), CancelOutcome::Idle);
+    }
+
+    #[test]
+    fn elevated_installer_cannot_be_cancelled() {
+        let control = InstallControl::default();
+        let token = control.start();
+        control.enter(InstallPhase::RunningElevated);
+
+        assert_eq!(control.cancel(false), CancelOutcome::NotCancellable);
+        assert_eq!(control.cancel(true), CancelOutcome::NotCancellable);
+        assert!(!token.is_cancelled());
+    }
+}
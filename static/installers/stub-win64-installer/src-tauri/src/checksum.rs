@@ -0,0 +1,85 @@
+//! SHA-256 digests published alongside Floorp releases.
+
+/// Normalises a SHA-256 digest to lowercase hex.
+///
+/// Accepts both the bare form and GitHub's `sha256:<hex>` asset digest form.
+/// Returns `None` for anything that is not a 64-digit hex string.
+pub fn parse_sha256(value: &str) -> Option<String> {
+    let value = value.trim();
+    let hex = value.strip_prefix("sha256:").unwrap_or(value);
+
+    if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(hex.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Finds the digest for `file_name` in `sha256sum`-style output.
+///
+/// Each line is `<hex>  <name>` (text mode) or `<hex> *<name>` (binary mode).
+/// A single bare digest, as found in most `.sha256` files, is also accepted.
+pub fn find_in_checksum_file(contents: &str, file_name: &str) -> Option<String> {
+    let lines: Vec<&str> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if let [only] = lines.as_slice() {
+        if let Some(digest) = parse_sha256(only) {
+            return Some(digest);
+        }
+    }
+
+    lines.iter().find_map(|line| {
+        let (digest, name) = line.split_once(char::is_whitespace)?;
+        let name = name.trim_start().trim_start_matches('*');
+        if name == file_name {
+            parse_sha256(digest)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn parses_github_asset_digest() {
+        let upper = DIGEST.to_ascii_uppercase();
+        assert_eq!(
+            parse_sha256(&format!("sha256:{}", DIGEST)).as_deref(),
+            Some(DIGEST)
+        );
+        assert_eq!(parse_sha256(&upper).as_deref(), Some(DIGEST));
+        assert_eq!(parse_sha256("sha256:1234"), None);
+        assert_eq!(parse_sha256(&format!("sha512:{}", DIGEST)), None);
+    }
+
+    #[test]
+    fn finds_entry_in_sums_file() {
+        let sums = format!(
+            "{}  floorp-linux-x86_64.tar.xz\n{} *floorp-windows-x86_64.installer.exe\n",
+            "0".repeat(64),
+            DIGEST
+        );
+        assert_eq!(
+            find_in_checksum_file(&sums, "floorp-windows-x86_64.installer.exe").as_deref(),
+            Some(DIGEST)
+        );
+        assert_eq!(find_in_checksum_file(&sums, "missing.exe"), None);
+    }
+
+    #[test]
+    fn accepts_bare_digest_file() {
+        assert_eq!(
+            find_in_checksum_file(&format!("{}\n", DIGEST), "anything.exe").as_deref(),
+            Some(DIGEST)
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! Command-line options shared by several modules.
+
+/// The last value of `flag` in `args`, given either as `--flag value` or
+/// `--flag=value`.
+pub(crate) fn arg_value(args: &[String], flag: &str) -> Result<Option<String>, String> {
+    let mut value = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            let next = args
+                .next()
+                .ok_or_else(|| format!("{} requires a value", flag))?;
+            value = Some(next.clone());
+        } else if let Some(inline) = arg
+            .strip_prefix(flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            value = Some(inline.to_string());
+        }
+    }
+
+    Ok(value)
+}
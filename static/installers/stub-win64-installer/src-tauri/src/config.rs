@@ -0,0 +1,233 @@
+//! Optional installer settings for managed deployments.
+//!
+//! Settings are read from `floorp-installer.json`, looked up next to the stub
+//! executable first and then in `%LOCALAPPDATA%\Floorp-Installer`. A missing
+//! file means every setting keeps its default.
+//!
+//! The stub usually runs from the Downloads folder, where any website can
+//! drop a file. The signing policies decide which installers are run, so
+//! they are only ever taken from the `%LOCALAPPDATA%` file; a file next to
+//! the stub sets everything else.
+
+use crate::cache::CacheConfig;
+use crate::download::DownloadOptions;
+use crate::http::{ProxyConfig, RetryPolicy, Timeouts, TlsConfig};
+use crate::release::{default_asset_patterns, AssetPattern, ReleaseSource};
+use crate::signing::{floorp_pins, microsoft_pins, TrustPolicy};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE_NAME: &str = "floorp-installer.json";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InstallerConfig {
+    /// Release sources, tried in order until one provides the installer.
+    pub release_sources: Vec<ReleaseSource>,
+    /// Installer file names per architecture, see `release::AssetSelector`.
+    pub asset_patterns: Vec<AssetPattern>,
+    /// Proxy for all network requests, see `http`.
+    pub proxy: ProxyConfig,
+    /// Extra trusted certificates, see `http::TlsConfig`.
+    pub tls: TlsConfig,
+    /// Connect, read and overall limits for network requests.
+    pub timeouts: Timeouts,
+    /// How often failed requests and downloads are retried.
+    pub retry: RetryPolicy,
+    /// How the installer is downloaded, see `download::DownloadOptions`.
+    pub download: DownloadOptions,
+    /// Reuse of verified installers, see `cache`.
+    pub cache: CacheConfig,
+    /// Certificates the installer may be signed with, see `signing`;
+    /// without pins, the compiled-in ones apply.
+    pub signing: TrustPolicy,
+    /// Certificates the WebView2 Runtime bootstrapper may be signed with;
+    /// without pins, Microsoft's compiled-in ones apply.
+    pub webview2_signing: TrustPolicy,
+}
+
+impl Default for InstallerConfig {
+    fn default() -> Self {
+        InstallerConfig {
+            release_sources: vec![ReleaseSource::default()],
+            asset_patterns: default_asset_patterns(),
+            proxy: ProxyConfig::default(),
+            tls: TlsConfig::default(),
+            timeouts: Timeouts::default(),
+            retry: RetryPolicy::default(),
+            download: DownloadOptions::default(),
+            cache: CacheConfig::default(),
+            signing: TrustPolicy::default().or_default_pins(floorp_pins),
+            webview2_signing: TrustPolicy::default().or_default_pins(microsoft_pins),
+        }
+    }
+}
+
+impl InstallerConfig {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut config: InstallerConfig =
+            serde_json::from_str(json).map_err(|e| format!("Invalid config: {}", e))?;
+        config.signing = config.signing.or_default_pins(floorp_pins);
+        config.webview2_signing = config.webview2_signing.or_default_pins(microsoft_pins);
+        if config.release_sources.is_empty() {
+            return Err("Invalid config: releaseSources must not be empty".to_string());
+        }
+        if config.asset_patterns.is_empty() {
+            return Err("Invalid config: assetPatterns must not be empty".to_string());
+        }
+        config
+            .timeouts
+            .validate()
+            .map_err(|e| format!("Invalid config: {}", e))?;
+        for policy in [&config.signing, &config.webview2_signing] {
+            policy
+                .validate()
+                .map_err(|e| format!("Invalid config: {}", e))?;
+        }
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::from_json(&json)
+    }
+
+    /// Takes the signing policies from `trusted`, for a config read from a
+    /// place anyone may have planted it in.
+    fn with_trust_from(mut self, trusted: InstallerConfig) -> Self {
+        let defaults = InstallerConfig::default();
+        if self.signing != defaults.signing || self.webview2_signing != defaults.webview2_signing {
+            println!(
+                "[WARN] Ignoring signing settings next to the stub; set them in %LOCALAPPDATA%\\Floorp-Installer\\{}",
+                CONFIG_FILE_NAME
+            );
+        }
+        self.signing = trusted.signing;
+        self.webview2_signing = trusted.webview2_signing;
+        self
+    }
+}
+
+fn exe_dir_config_path() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    Some(exe.parent()?.join(CONFIG_FILE_NAME))
+}
+
+fn local_config_path() -> Option<PathBuf> {
+    let local_appdata = env::var("LOCALAPPDATA").ok()?;
+    Some(
+        PathBuf::from(local_appdata)
+            .join("Floorp-Installer")
+            .join(CONFIG_FILE_NAME),
+    )
+}
+
+fn load_file(path: &Path) -> Option<InstallerConfig> {
+    if !path.exists() {
+        return None;
+    }
+    match InstallerConfig::from_file(path) {
+        Ok(config) => {
+            println!("[INFO] Loaded installer config: {}", path.display());
+            Some(config)
+        }
+        Err(e) => {
+            println!("[WARN] Ignoring installer config: {}", e);
+            None
+        }
+    }
+}
+
+/// Loads the config next to the stub, or else the one in `%LOCALAPPDATA%`,
+/// falling back to the defaults. The signing policies always come from the
+/// latter.
+pub fn load() -> InstallerConfig {
+    let local = local_config_path().and_then(|path| load_file(&path));
+    match exe_dir_config_path().and_then(|path| load_file(&path)) {
+        Some(config) => config.with_trust_from(local.unwrap_or_default()),
+        None => local.unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::RevocationCheck;
+
+    #[test]
+    fn missing_keys_use_defaults() {
+        let config = InstallerConfig::from_json("{}").unwrap();
+        assert_eq!(config.release_sources, vec![ReleaseSource::default()]);
+        assert_eq!(config.asset_patterns, default_asset_patterns());
+    }
+
+    #[test]
+    fn rejects_empty_source_list() {
+        assert!(InstallerConfig::from_json(r#"{ "releaseSources": [] }"#).is_err());
+    }
+
+    #[test]
+    fn reads_network_limits() {
+        let config = InstallerConfig::from_json(
+            r#"{ "timeouts": { "readSecs": 60 }, "retry": { "maxAttempts": 1 } }"#,
+        )
+        .unwrap();
+        assert_eq!(config.timeouts.read_secs, 60);
+        assert_eq!(
+            config.timeouts.connect_secs,
+            Timeouts::default().connect_secs
+        );
+        assert!(!config.retry.allows_retry(1));
+
+        assert!(InstallerConfig::from_json(r#"{ "timeouts": { "connectSecs": 0 } }"#).is_err());
+    }
+
+    #[test]
+    fn takes_signing_policies_only_from_the_trusted_config() {
+        let planted = InstallerConfig::from_json(
+            r#"{
+                "retry": { "maxAttempts": 1 },
+                "signing": {
+                    "pins": [{ "label": "any", "spkiSha256": "5f070c858afca41bf6ffad4bc07b342a34b651e3a7cc6c3d5583d0966e445c51" }],
+                    "revocation": "off"
+                },
+                "webview2Signing": { "revocation": "off" }
+            }"#,
+        )
+        .unwrap();
+        let trusted =
+            InstallerConfig::from_json(r#"{ "signing": { "strictRevocation": true } }"#).unwrap();
+
+        let config = planted.with_trust_from(trusted.clone());
+        assert!(!config.retry.allows_retry(1));
+        assert_eq!(config.signing, trusted.signing);
+        assert_eq!(
+            config.webview2_signing,
+            InstallerConfig::default().webview2_signing
+        );
+    }
+
+    #[test]
+    fn checks_both_signing_policies() {
+        let config = InstallerConfig::from_json(
+            r#"{ "webview2Signing": { "pins": [{ "label": "Microsoft", "spkiSha256": "00" }] } }"#,
+        );
+        assert!(config
+            .unwrap_err()
+            .contains("pin Microsoft has an invalid SHA-256"));
+
+        let config =
+            InstallerConfig::from_json(r#"{ "webview2Signing": { "revocation": "off" } }"#)
+                .unwrap();
+        assert_eq!(config.webview2_signing.revocation, RevocationCheck::Off);
+        assert_eq!(config.webview2_signing.pins, microsoft_pins());
+        assert_eq!(config.signing, InstallerConfig::default().signing);
+
+        let config =
+            InstallerConfig::from_json(r#"{ "signing": { "revocation": "off" } }"#).unwrap();
+        assert_eq!(config.signing.pins, floorp_pins());
+    }
+}
@@ -0,0 +1,1067 @@
+//! Streaming installer download with progress reporting.
+
+mod segmented;
+mod throttle;
+
+use crate::{cli, http};
+use reqwest::header::{HeaderName, CONTENT_RANGE, ETAG, IF_RANGE, RANGE};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use throttle::Throttle;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::time::sleep;
+
+pub const MAX_RATE_ARG: &str = "--max-download-rate";
+pub const SEGMENTS_ARG: &str = "--download-segments";
+
+/// Tauri event name used to forward [`DownloadProgress`] to the frontend.
+pub const PROGRESS_EVENT: &str = "download-progress";
+
+/// Minimum time between two progress reports, so the UI is not flooded.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the resume data is refreshed while a download is running.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Weight of the newest sample in the smoothed transfer speed.
+const SPEED_SMOOTHING: f64 = 0.3;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub bytes_per_second: u64,
+    pub eta_seconds: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DownloadOptions {
+    /// Byte ranges fetched at the same time. `1`, the default, keeps a
+    /// single stream.
+    pub segments: u32,
+    /// Transfer rate cap in bytes per second, shared by all segments.
+    pub max_bytes_per_second: Option<u64>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadOptions {
+            segments: 1,
+            max_bytes_per_second: None,
+        }
+    }
+}
+
+impl DownloadOptions {
+    /// Applies `--max-download-rate <bytes per second>` from `args`, where
+    /// `0` lifts the cap, and `--download-segments <count>`.
+    pub fn with_overrides(mut self, args: &[String]) -> Result<Self, String> {
+        if let Some(value) = cli::arg_value(args, MAX_RATE_ARG)? {
+            let rate: u64 = value
+                .parse()
+                .map_err(|_| format!("invalid {} value: {}", MAX_RATE_ARG, value))?;
+            self.max_bytes_per_second = Some(rate).filter(|rate| *rate > 0);
+        }
+        if let Some(value) = cli::arg_value(args, SEGMENTS_ARG)? {
+            self.segments = value
+                .parse()
+                .ok()
+                .filter(|segments| *segments > 0)
+                .ok_or_else(|| format!("invalid {} value: {}", SEGMENTS_ARG, value))?;
+        }
+        Ok(self)
+    }
+}
+
+/// A completed download.
+#[derive(Clone, Debug)]
+pub struct DownloadedFile {
+    pub len: u64,
+    /// Lowercase hex SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+/// Why a download failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DownloadError {
+    /// The TLS connection failed, see [`crate::http::tls_problem`].
+    Tls(String),
+    /// The transfer broke off; what arrived is kept for resuming.
+    Interrupted(String),
+    /// The response ended before `Content-Length` bytes had arrived; what
+    /// arrived is kept for resuming.
+    Incomplete {
+        got: u64,
+        expected: u64,
+    },
+    /// The drive ran out of space; the partial file has been removed.
+    /// `needed` is the size of the whole file, or a lower bound if the
+    /// server did not announce one.
+    DiskFull {
+        needed: u64,
+    },
+    Failed(String),
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::Tls(problem) => write!(f, "TLS connection failed: {}", problem),
+            DownloadError::Incomplete { got, expected } => {
+                write!(f, "Download incomplete: {} of {} bytes", got, expected)
+            }
+            DownloadError::DiskFull { needed } => {
+                write!(f, "Not enough disk space for {} bytes", needed)
+            }
+            DownloadError::Interrupted(reason) | DownloadError::Failed(reason) => {
+                f.write_str(reason)
+            }
+        }
+    }
+}
+
+impl From<String> for DownloadError {
+    fn from(reason: String) -> Self {
+        DownloadError::Failed(reason)
+    }
+}
+
+/// Turns a stream of byte counts into rate-limited progress snapshots.
+struct ProgressTracker {
+    total: Option<u64>,
+    downloaded: u64,
+    last_report: Instant,
+    last_reported_bytes: u64,
+    speed: Option<f64>,
+}
+
+impl ProgressTracker {
+    fn new(total: Option<u64>, already_downloaded: u64) -> Self {
+        ProgressTracker {
+            total,
+            downloaded: already_downloaded,
+            last_report: Instant::now(),
+            last_reported_bytes: already_downloaded,
+            speed: None,
+        }
+    }
+
+    /// Records `len` more bytes and returns a snapshot when one is due.
+    fn advance(&mut self, len: u64) -> Option<DownloadProgress> {
+        self.downloaded += len;
+
+        let elapsed = self.last_report.elapsed();
+        if elapsed < REPORT_INTERVAL {
+            return None;
+        }
+
+        let sample = (self.downloaded - self.last_reported_bytes) as f64 / elapsed.as_secs_f64();
+        self.speed = Some(match self.speed {
+            Some(speed) => speed + SPEED_SMOOTHING * (sample - speed),
+            None => sample,
+        });
+        self.last_report = Instant::now();
+        self.last_reported_bytes = self.downloaded;
+
+        Some(self.snapshot())
+    }
+
+    fn snapshot(&self) -> DownloadProgress {
+        let bytes_per_second = self.speed.unwrap_or(0.0) as u64;
+        let eta_seconds = match self.total {
+            Some(total) if bytes_per_second > 0 => {
+                Some(total.saturating_sub(self.downloaded) / bytes_per_second)
+            }
+            _ => None,
+        };
+
+        DownloadProgress {
+            downloaded: self.downloaded,
+            total: self.total,
+            bytes_per_second,
+            eta_seconds,
+        }
+    }
+}
+
+/// Resume information stored next to a `.part` file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PartialDownload {
+    url: String,
+    etag: Option<String>,
+    bytes_written: u64,
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Returns the resume state for `url`, or `None` if the partial file is
+/// missing, belongs to another URL, or cannot be validated with an ETag.
+async fn load_partial(part_path: &Path, meta_path: &Path, url: &str) -> Option<PartialDownload> {
+    let meta = fs::read(meta_path).await.ok()?;
+    let mut partial: PartialDownload = serde_json::from_slice(&meta).ok()?;
+    let on_disk = fs::metadata(part_path).await.ok()?.len();
+
+    if partial.url != url || partial.etag.is_none() || partial.bytes_written == 0 {
+        return None;
+    }
+
+    partial.bytes_written = partial.bytes_written.min(on_disk);
+    Some(partial)
+}
+
+async fn save_partial(meta_path: &Path, partial: &PartialDownload) -> Result<(), String> {
+    let json = serde_json::to_vec(partial).map_err(|e| format!("Resume data error: {}", e))?;
+    fs::write(meta_path, json)
+        .await
+        .map_err(|e| format!("Resume data write error: {}", e))
+}
+
+/// Feeds the first `len` bytes of `path` into `hasher`, so a resumed
+/// download ends up with the digest of the whole file.
+async fn hash_prefix(path: &Path, len: u64, hasher: &mut Sha256) -> Result<(), String> {
+    let mut file = File::open(path)
+        .await
+        .map_err(|e| format!("File open error: {}", e))?
+        .take(len);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("File read error: {}", e))?;
+        if n == 0 {
+            return Ok(());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+/// Parses the start offset out of a `Content-Range: bytes a-b/n` header.
+fn content_range_start(value: &str) -> Option<u64> {
+    let (start, _) = value.strip_prefix("bytes ")?.split_once('-')?;
+    start.parse().ok()
+}
+
+/// Whether `err` means the drive is out of space.
+fn is_disk_full(err: &io::Error) -> bool {
+    const ERROR_HANDLE_DISK_FULL: i32 = 39;
+    const ERROR_DISK_FULL: i32 = 112;
+    const ENOSPC: i32 = 28;
+
+    match err.raw_os_error() {
+        Some(code) if cfg!(windows) => code == ERROR_HANDLE_DISK_FULL || code == ERROR_DISK_FULL,
+        Some(code) => code == ENOSPC,
+        None => false,
+    }
+}
+
+/// Maps a failed write of a file that is to hold `needed` bytes.
+fn write_failed(err: io::Error, needed: u64) -> DownloadError {
+    if is_disk_full(&err) {
+        DownloadError::DiskFull { needed }
+    } else {
+        DownloadError::Failed(format!("File write error: {}", err))
+    }
+}
+
+fn header_str(resp: &reqwest::Response, name: HeaderName) -> Option<&str> {
+    resp.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+fn request_failed(err: http::SendError) -> DownloadError {
+    match err.tls_problem() {
+        Some(problem) => DownloadError::Tls(problem),
+        None => DownloadError::Failed(format!("HTTP request error: {}", err)),
+    }
+}
+
+/// Sends the request for `url`, asking for the remainder of `partial` when
+/// one is available. Returns the response together with the offset its body
+/// starts at; servers that ignore the range or report a changed ETag answer
+/// with the full body, in which case the offset is zero.
+async fn request_body(
+    client: &Client,
+    url: &str,
+    partial: Option<&PartialDownload>,
+) -> Result<(reqwest::Response, u64), DownloadError> {
+    let mut request = client.get(url);
+    if let Some(partial) = partial {
+        request = request
+            .header(RANGE, format!("bytes={}-", partial.bytes_written))
+            .header(IF_RANGE, partial.etag.as_deref().unwrap_or_default());
+    }
+
+    let resp = http::send(request).await.map_err(request_failed)?;
+
+    if let Some(partial) = partial {
+        if resp.status() == StatusCode::PARTIAL_CONTENT {
+            let start = header_str(&resp, CONTENT_RANGE).and_then(content_range_start);
+            if start == Some(partial.bytes_written) {
+                println!("[INFO] Resuming download at byte {}", partial.bytes_written);
+                return Ok((resp, partial.bytes_written));
+            }
+        }
+
+        if resp.status() != StatusCode::OK {
+            println!(
+                "[WARN] Server did not honour the resume request ({}), restarting download",
+                resp.status()
+            );
+            return Box::pin(request_body(client, url, None)).await;
+        }
+
+        println!("[INFO] Server sent the full file, restarting download");
+    }
+
+    let resp = resp
+        .error_for_status()
+        .map_err(|e| format!("HTTP status error: {}", e))?;
+    Ok((resp, 0))
+}
+
+/// Downloads `url` into `path`, writing each chunk as it arrives.
+///
+/// Data is first written to `<path>.part`, with the URL, ETag and byte count
+/// kept in `<path>.part.json`. If a previous attempt left such a pair behind,
+/// the transfer continues from where it stopped using `Range`/`If-Range`.
+/// Only a file of the announced length is synced to disk and renamed to
+/// `path`, so `path` never holds a truncated download.
+///
+/// The SHA-256 digest is computed while the data streams in, including the
+/// bytes carried over from a resumed attempt.
+///
+/// `on_progress` is called at most every [`REPORT_INTERVAL`] and once more
+/// when the transfer completes.
+///
+/// A transfer that breaks off is resumed under the shared retry policy.
+///
+/// Unless there is a partial file to resume, a file large enough is fetched
+/// as up to `options.segments` byte ranges at the same time, falling back
+/// to a single stream when the server does not serve ranges.
+pub async fn download_file<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    options: &DownloadOptions,
+    mut on_progress: F,
+) -> Result<DownloadedFile, DownloadError>
+where
+    F: FnMut(&DownloadProgress),
+{
+    let resumable = load_partial(
+        &sibling_path(path, ".part"),
+        &sibling_path(path, ".part.json"),
+        url,
+    )
+    .await
+    .is_some();
+
+    let throttle = options.max_bytes_per_second.map(|rate| {
+        println!("[INFO] Download rate capped at {} bytes/s", rate);
+        Arc::new(Throttle::new(rate))
+    });
+
+    if options.segments > 1 && !resumable {
+        let plan = segmented::probe(client, url)
+            .await?
+            .map(|file| file.split(options.segments))
+            .filter(|plan| plan.segments.len() > 1);
+        if let Some(plan) = plan {
+            match segmented::download(client, url, path, &plan, &throttle, &mut on_progress).await {
+                Err(DownloadError::Failed(reason)) | Err(DownloadError::Interrupted(reason)) => {
+                    println!(
+                        "[WARN] Segmented download failed: {}, falling back to a single stream",
+                        reason
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+
+    let policy = http::retry_policy();
+    let mut attempt = 1;
+
+    loop {
+        match download_attempt(client, url, path, throttle.as_deref(), &mut on_progress).await {
+            Err(e @ (DownloadError::Interrupted(_) | DownloadError::Incomplete { .. }))
+                if policy.allows_retry(attempt) =>
+            {
+                let delay = policy.backoff(attempt);
+                println!(
+                    "[WARN] Download interrupted: {} (attempt {}/{}), resuming in {} ms",
+                    e,
+                    attempt,
+                    policy.max_attempts,
+                    delay.as_millis()
+                );
+                sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn download_attempt<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    throttle: Option<&Throttle>,
+    on_progress: &mut F,
+) -> Result<DownloadedFile, DownloadError>
+where
+    F: FnMut(&DownloadProgress),
+{
+    let part_path = sibling_path(path, ".part");
+    let meta_path = sibling_path(path, ".part.json");
+
+    let partial = load_partial(&part_path, &meta_path, url).await;
+    let (mut resp, offset) = request_body(client, url, partial.as_ref()).await?;
+
+    let mut hasher = Sha256::new();
+    let mut file = if offset > 0 {
+        hash_prefix(&part_path, offset, &mut hasher).await?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(&part_path)
+            .await
+            .map_err(|e| format!("File open error: {}", e))?;
+        file.set_len(offset)
+            .await
+            .map_err(|e| format!("File write error: {}", e))?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|e| format!("File write error: {}", e))?;
+        file
+    } else {
+        File::create(&part_path)
+            .await
+            .map_err(|e| format!("File creation error: {}", e))?
+    };
+
+    let mut state = PartialDownload {
+        url: url.to_string(),
+        etag: header_str(&resp, ETAG).map(str::to_string),
+        bytes_written: offset,
+    };
+    save_partial(&meta_path, &state).await?;
+
+    let total = resp.content_length().map(|len| len + offset);
+    let mut tracker = ProgressTracker::new(total, offset);
+    on_progress(&tracker.snapshot());
+
+    let mut last_checkpoint = Instant::now();
+    let result = loop {
+        let chunk = match http::next_chunk(&mut resp).await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(DownloadError::Interrupted(e)),
+        };
+        if let Some(throttle) = throttle {
+            throttle.pace(chunk.len() as u64).await;
+        }
+
+        if let Err(e) = file.write_all(&chunk).await {
+            let needed = state.bytes_written + chunk.len() as u64;
+            break Err(write_failed(e, total.unwrap_or(needed)));
+        }
+        hasher.update(&chunk);
+        state.bytes_written += chunk.len() as u64;
+
+        if let Some(progress) = tracker.advance(chunk.len() as u64) {
+            on_progress(&progress);
+        }
+
+        if last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL && file.flush().await.is_ok() {
+            let _ = save_partial(&meta_path, &state).await;
+            last_checkpoint = Instant::now();
+        }
+    };
+
+    // A body shorter than announced is not always reported as an error.
+    let result = match (result, total) {
+        (Err(DownloadError::Interrupted(reason)), Some(expected)) => {
+            println!("[WARN] Connection lost: {}", reason);
+            Err(DownloadError::Incomplete {
+                got: state.bytes_written,
+                expected,
+            })
+        }
+        (Ok(()), Some(expected)) if state.bytes_written != expected => {
+            Err(DownloadError::Incomplete {
+                got: state.bytes_written,
+                expected,
+            })
+        }
+        (result, _) => result,
+    };
+
+    let needed = total.unwrap_or(state.bytes_written);
+    let flushed = match result {
+        Ok(()) => file.flush().await.map_err(|e| write_failed(e, needed)),
+        Err(e) => Err(e),
+    };
+    let synced = match flushed {
+        Ok(()) => file.sync_all().await.map_err(|e| write_failed(e, needed)),
+        Err(e) => Err(e),
+    };
+    drop(file);
+    match synced {
+        Ok(()) => {}
+        Err(e @ DownloadError::DiskFull { .. }) => {
+            // Nothing can be resumed until space is freed, and the partial
+            // file only adds to the shortage.
+            let _ = fs::remove_file(&part_path).await;
+            let _ = fs::remove_file(&meta_path).await;
+            return Err(e);
+        }
+        Err(e) => {
+            let _ = save_partial(&meta_path, &state).await;
+            return Err(e);
+        }
+    }
+
+    fs::rename(&part_path, path)
+        .await
+        .map_err(|e| format!("File rename error: {}", e))?;
+    let _ = fs::remove_file(&meta_path).await;
+
+    on_progress(&tracker.snapshot());
+
+    Ok(DownloadedFile {
+        len: tracker.downloaded,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+/// Removes `path` along with any partial download and resume data left
+/// for it.
+pub async fn discard(path: &Path) {
+    for file in [
+        path.to_path_buf(),
+        sibling_path(path, ".part"),
+        sibling_path(path, ".part.json"),
+    ] {
+        let _ = fs::remove_file(file).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Request, Response, TestServer};
+    use std::sync::{Arc, Mutex};
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    const SINGLE_STREAM: DownloadOptions = DownloadOptions {
+        segments: 1,
+        max_bytes_per_second: None,
+    };
+
+    fn segmented(segments: u32) -> DownloadOptions {
+        DownloadOptions {
+            segments,
+            ..DownloadOptions::default()
+        }
+    }
+
+    /// Serves `body` with `etag`, honouring `Range` unless `If-Range` names
+    /// another ETag.
+    fn serve_ranges(body: &[u8], etag: &str, req: &Request) -> Response {
+        let last = body.len().saturating_sub(1);
+        let range = req
+            .header("Range")
+            .filter(|_| req.header("If-Range").filter(|tag| *tag != etag).is_none())
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.split_once('-'))
+            .and_then(|(start, end)| {
+                let end = match end {
+                    "" => last,
+                    end => end.parse::<usize>().ok()?.min(last),
+                };
+                Some((start.parse::<usize>().ok()?, end))
+            });
+
+        match range {
+            Some((start, end)) if start <= end => Response::ok(body[start..=end].to_vec())
+                .with_status(206)
+                .with_header(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", start, end, body.len()),
+                )
+                .with_header("ETag", etag),
+            _ => Response::ok(body.to_vec()).with_header("ETag", etag),
+        }
+    }
+
+    fn seed_partial(path: &Path, url: &str, etag: &str, data: &[u8]) {
+        std::fs::write(sibling_path(path, ".part"), data).unwrap();
+        let partial = PartialDownload {
+            url: url.to_string(),
+            etag: Some(etag.to_string()),
+            bytes_written: data.len() as u64,
+        };
+        std::fs::write(
+            sibling_path(path, ".part.json"),
+            serde_json::to_vec(&partial).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn streams_large_file_to_disk_with_progress() {
+        let body = payload(8 * 1024 * 1024);
+        let served = body.clone();
+        let server = TestServer::start(move |req| match req.path.as_str() {
+            "/installer.exe" => Response::ok(served.clone()),
+            _ => Response::status(404),
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+        let mut reports = Vec::new();
+
+        let downloaded = download_file(
+            &Client::new(),
+            &server.url("/installer.exe"),
+            &path,
+            &SINGLE_STREAM,
+            |p| reports.push(p.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(downloaded.len, body.len() as u64);
+        assert_eq!(downloaded.sha256, hex::encode(Sha256::digest(&body)));
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+
+        let last = reports.last().unwrap();
+        assert_eq!(last.downloaded, body.len() as u64);
+        assert_eq!(last.total, Some(body.len() as u64));
+        assert!(reports
+            .windows(2)
+            .all(|pair| pair[0].downloaded <= pair[1].downloaded));
+    }
+
+    #[tokio::test]
+    async fn http_error_status_fails_download() {
+        let server = TestServer::start(|_| Response::status(404));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+
+        let err = download_file(
+            &Client::new(),
+            &server.url("/missing"),
+            &path,
+            &SINGLE_STREAM,
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.to_string().starts_with("HTTP status error"), "{}", err);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn resumes_partial_download_with_range_request() {
+        let body = payload(2 * 1024 * 1024);
+        let served = body.clone();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&ranges);
+        let server = TestServer::start(move |req| {
+            seen.lock()
+                .unwrap()
+                .push(req.header("Range").map(str::to_string));
+            serve_ranges(&served, "\"v1\"", req)
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+        let url = server.url("/installer.exe");
+        seed_partial(&path, &url, "\"v1\"", &body[..1000]);
+
+        let mut first = None;
+        let downloaded = download_file(&Client::new(), &url, &path, &SINGLE_STREAM, |p| {
+            first.get_or_insert(p.downloaded);
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(downloaded.len, body.len() as u64);
+        assert_eq!(downloaded.sha256, hex::encode(Sha256::digest(&body)));
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(first, Some(1000));
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            vec![Some("bytes=1000-".to_string())]
+        );
+        assert!(!sibling_path(&path, ".part").exists());
+        assert!(!sibling_path(&path, ".part.json").exists());
+    }
+
+    #[tokio::test]
+    async fn resumes_after_the_connection_drops() {
+        let body = payload(256 * 1024);
+        let served = body.clone();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&ranges);
+        let server = TestServer::start(move |req| {
+            let mut seen = seen.lock().unwrap();
+            seen.push(req.header("Range").map(str::to_string));
+            let response = serve_ranges(&served, "\"v1\"", req);
+            if seen.len() == 1 {
+                response.cut_after(10_000)
+            } else {
+                response
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+
+        let downloaded = download_file(
+            &Client::new(),
+            &server.url("/installer.exe"),
+            &path,
+            &SINGLE_STREAM,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(downloaded.sha256, hex::encode(Sha256::digest(&body)));
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            vec![None, Some("bytes=10000-".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_large_file_in_parallel_segments() {
+        let body = payload(8 * 1024 * 1024 + 123);
+        let served = body.clone();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&ranges);
+        let server = TestServer::start(move |req| {
+            seen.lock().unwrap().push((
+                req.header("Range").map(str::to_string),
+                req.header("If-Range").map(str::to_string),
+            ));
+            serve_ranges(&served, "\"v1\"", req)
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+        let options = segmented(4);
+        let mut reports = Vec::new();
+
+        let downloaded = download_file(
+            &Client::new(),
+            &server.url("/installer.exe"),
+            &path,
+            &options,
+            |p| reports.push(p.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(downloaded.len, body.len() as u64);
+        assert_eq!(downloaded.sha256, hex::encode(Sha256::digest(&body)));
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert!(!sibling_path(&path, ".part").exists());
+
+        let last = reports.last().unwrap();
+        assert_eq!(last.downloaded, body.len() as u64);
+        assert_eq!(last.total, Some(body.len() as u64));
+
+        let mut ranges = ranges.lock().unwrap().clone();
+        ranges.sort();
+        let segment = |range: &str| (Some(range.to_string()), Some("\"v1\"".to_string()));
+        assert_eq!(
+            ranges,
+            vec![
+                (Some("bytes=0-0".to_string()), None),
+                segment("bytes=0-2097182"),
+                segment("bytes=2097183-4194365"),
+                segment("bytes=4194366-6291548"),
+                segment("bytes=6291549-8388730"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn resumes_a_segment_after_the_connection_drops() {
+        let body = payload(4 * 1024 * 1024);
+        let served = body.clone();
+        let requests = Arc::new(Mutex::new(0));
+        let seen = Arc::clone(&requests);
+        let server = TestServer::start(move |req| {
+            let mut seen = seen.lock().unwrap();
+            *seen += 1;
+            let response = serve_ranges(&served, "\"v1\"", req);
+            // The first segment request after the probe breaks off.
+            if *seen == 2 {
+                response.cut_after(10_000)
+            } else {
+                response
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+
+        let downloaded = download_file(
+            &Client::new(),
+            &server.url("/installer.exe"),
+            &path,
+            &segmented(4),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(downloaded.sha256, hex::encode(Sha256::digest(&body)));
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(*requests.lock().unwrap(), 6);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_one_stream_without_range_support() {
+        let body = payload(4 * 1024 * 1024);
+        let served = body.clone();
+        let requests = Arc::new(Mutex::new(0));
+        let seen = Arc::clone(&requests);
+        let server = TestServer::start(move |_| {
+            *seen.lock().unwrap() += 1;
+            Response::ok(served.clone())
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+
+        let downloaded = download_file(
+            &Client::new(),
+            &server.url("/installer.exe"),
+            &path,
+            &segmented(4),
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(downloaded.sha256, hex::encode(Sha256::digest(&body)));
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(*requests.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn caps_the_transfer_rate_without_bursts() {
+        const RATE: u64 = 128 * 1024;
+        let body = payload(192 * 1024);
+        let served = body.clone();
+        let server = TestServer::start(move |_| Response::ok(served.clone()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+        let options = DownloadOptions {
+            segments: 1,
+            max_bytes_per_second: Some(RATE),
+        };
+        let started = Instant::now();
+        let mut reports = Vec::new();
+
+        download_file(
+            &Client::new(),
+            &server.url("/installer.exe"),
+            &path,
+            &options,
+            |p| reports.push((started.elapsed(), p.downloaded)),
+        )
+        .await
+        .unwrap();
+
+        // 192 KiB at 128 KiB/s take a second and a half.
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(1400), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+
+        // At no point is more than one read ahead of the cap.
+        for (at, downloaded) in reports {
+            let allowed = (at.as_secs_f64() * RATE as f64) as u64 + 64 * 1024;
+            assert!(downloaded <= allowed, "{} bytes after {:?}", downloaded, at);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_capped_download_outlives_the_total_timeout() {
+        let body = payload(192 * 1024);
+        let served = body.clone();
+        let server = TestServer::start(move |_| Response::ok(served.clone()));
+        let settings = http::HttpSettings {
+            proxy: http::ProxyConfig {
+                url: Some(http::DIRECT.to_string()),
+                ..http::ProxyConfig::default()
+            },
+            timeouts: http::Timeouts {
+                total_secs: 1,
+                ..http::Timeouts::default()
+            },
+            ..http::HttpSettings::default()
+        };
+        let client = http::client_builder(&settings).unwrap().build().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+        let options = DownloadOptions {
+            segments: 1,
+            max_bytes_per_second: Some(128 * 1024),
+        };
+        let started = Instant::now();
+
+        download_file(
+            &client,
+            &server.url("/installer.exe"),
+            &path,
+            &options,
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert!(started.elapsed() > Duration::from_secs(1));
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+    }
+
+    #[test]
+    fn reads_the_rate_cap_and_segments_from_the_command_line() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let options = |values: &[&str]| DownloadOptions::default().with_overrides(&args(values));
+
+        assert_eq!(
+            options(&["--max-download-rate", "500000"])
+                .unwrap()
+                .max_bytes_per_second,
+            Some(500_000)
+        );
+        assert_eq!(
+            options(&["--max-download-rate=0"])
+                .unwrap()
+                .max_bytes_per_second,
+            None
+        );
+        assert!(options(&["--max-download-rate=fast"]).is_err());
+
+        assert_eq!(options(&[]).unwrap().segments, 1);
+        assert_eq!(options(&["--download-segments", "4"]).unwrap().segments, 4);
+        assert!(options(&["--download-segments=0"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn reports_a_download_that_keeps_breaking_off_as_incomplete() {
+        let body = payload(64 * 1024);
+        let served = body.clone();
+        let server =
+            TestServer::start(move |req| serve_ranges(&served, "\"v1\"", req).cut_after(1000));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+
+        let err = download_file(
+            &Client::new(),
+            &server.url("/installer.exe"),
+            &path,
+            &SINGLE_STREAM,
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+
+        let attempts = u64::from(http::retry_policy().max_attempts);
+        assert_eq!(
+            err,
+            DownloadError::Incomplete {
+                got: attempts * 1000,
+                expected: body.len() as u64,
+            }
+        );
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::metadata(sibling_path(&path, ".part"))
+                .unwrap()
+                .len(),
+            attempts * 1000
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn recognises_a_full_disk() {
+        let mut full = std::fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/full")
+            .unwrap();
+        let err = std::io::Write::write_all(&mut full, b"installer").unwrap_err();
+
+        assert_eq!(write_failed(err, 9), DownloadError::DiskFull { needed: 9 });
+        assert!(!is_disk_full(&io::Error::other("unrelated")));
+    }
+
+    #[tokio::test]
+    async fn restarts_when_etag_has_changed() {
+        let body = payload(64 * 1024);
+        let served = body.clone();
+        let server = TestServer::start(move |req| serve_ranges(&served, "\"v2\"", req));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+        let url = server.url("/installer.exe");
+        seed_partial(&path, &url, "\"v1\"", &[0xff; 4096]);
+
+        download_file(&Client::new(), &url, &path, &SINGLE_STREAM, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn restarts_when_server_ignores_ranges() {
+        let body = payload(64 * 1024);
+        let served = body.clone();
+        let server =
+            TestServer::start(move |_| Response::ok(served.clone()).with_header("ETag", "\"v1\""));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("installer.exe");
+        let url = server.url("/installer.exe");
+        seed_partial(&path, &url, "\"v1\"", &[0xff; 4096]);
+
+        download_file(&Client::new(), &url, &path, &SINGLE_STREAM, |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+    }
+}
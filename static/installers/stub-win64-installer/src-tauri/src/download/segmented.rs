@@ -0,0 +1,317 @@
+//! Fetching one file as several byte ranges at the same time, which uses
+//! high-latency links far better than a single TCP stream.
+
+use super::{
+    content_range_start, hash_prefix, header_str, request_failed, sibling_path, write_failed,
+    DownloadError, DownloadProgress, DownloadedFile, ProgressTracker, Throttle,
+};
+use crate::http;
+use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, RANGE};
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task::JoinSet;
+use tokio::time::sleep;
+
+/// Smallest range worth a connection of its own.
+const MIN_SEGMENT_LEN: u64 = 1024 * 1024;
+
+/// A file the server serves in byte ranges.
+pub(super) struct RangedFile {
+    len: u64,
+    etag: Option<String>,
+}
+
+/// How a [`RangedFile`] is divided between connections.
+pub(super) struct SegmentPlan {
+    len: u64,
+    etag: Option<String>,
+    pub segments: Vec<Range<u64>>,
+}
+
+impl RangedFile {
+    /// Splits the file into at most `segments` ranges of nearly equal
+    /// length, none of them shorter than [`MIN_SEGMENT_LEN`] but the last.
+    pub fn split(self, segments: u32) -> SegmentPlan {
+        let count = (self.len / MIN_SEGMENT_LEN).clamp(1, u64::from(segments.max(1)));
+        let segment_len = self.len.div_ceil(count);
+        let segments = (0..count)
+            .map(|i| i * segment_len..((i + 1) * segment_len).min(self.len))
+            .filter(|range| !range.is_empty())
+            .collect();
+
+        SegmentPlan {
+            len: self.len,
+            etag: self.etag,
+            segments,
+        }
+    }
+}
+
+/// Parses the complete length out of a `Content-Range: bytes a-b/n` header.
+fn content_range_len(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.parse().ok()
+}
+
+/// Asks for the first byte of `url` to learn whether the server serves
+/// ranges, and if it does, how long the file is.
+pub(super) async fn probe(client: &Client, url: &str) -> Result<Option<RangedFile>, DownloadError> {
+    let resp = http::send(client.get(url).header(RANGE, "bytes=0-0"))
+        .await
+        .map_err(request_failed)?;
+
+    if resp.status() != StatusCode::PARTIAL_CONTENT {
+        resp.error_for_status()
+            .map_err(|e| format!("HTTP status error: {}", e))?;
+        return Ok(None);
+    }
+
+    Ok(header_str(&resp, CONTENT_RANGE)
+        .and_then(content_range_len)
+        .map(|len| RangedFile {
+            len,
+            etag: header_str(&resp, ETAG).map(str::to_string),
+        }))
+}
+
+/// Downloads `plan` into `path` with one connection per segment, each
+/// writing to its place in `<path>.part`. As the segments arrive out of
+/// order, the digest is computed once the file is complete.
+pub(super) async fn download<F>(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    plan: &SegmentPlan,
+    throttle: &Option<Arc<Throttle>>,
+    on_progress: &mut F,
+) -> Result<DownloadedFile, DownloadError>
+where
+    F: FnMut(&DownloadProgress),
+{
+    println!(
+        "[INFO] Downloading {} bytes in {} segments",
+        plan.len,
+        plan.segments.len()
+    );
+
+    let part_path = sibling_path(path, ".part");
+    let file = File::create(&part_path)
+        .await
+        .map_err(|e| format!("File creation error: {}", e))?;
+    if let Err(e) = file.set_len(plan.len).await {
+        drop(file);
+        let _ = fs::remove_file(&part_path).await;
+        return Err(write_failed(e, plan.len));
+    }
+    drop(file);
+
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let mut tasks = JoinSet::new();
+    for range in &plan.segments {
+        let segment = Segment {
+            client: client.clone(),
+            url: url.to_string(),
+            etag: plan.etag.clone(),
+            part_path: part_path.clone(),
+            file_len: plan.len,
+            range: range.clone(),
+            throttle: throttle.clone(),
+        };
+        tasks.spawn(fetch_segment(segment, progress_tx.clone()));
+    }
+    drop(progress_tx);
+
+    let mut tracker = ProgressTracker::new(Some(plan.len), 0);
+    on_progress(&tracker.snapshot());
+
+    let mut result = Ok(());
+    while result.is_ok() {
+        tokio::select! {
+            Some(len) = progress_rx.recv() => {
+                if let Some(progress) = tracker.advance(len) {
+                    on_progress(&progress);
+                }
+            }
+            joined = tasks.join_next() => match joined {
+                Some(Ok(Ok(()))) => {}
+                Some(Ok(Err(e))) => result = Err(e),
+                Some(Err(e)) => {
+                    result = Err(DownloadError::Failed(format!("Segment task failed: {}", e)))
+                }
+                None => break,
+            }
+        }
+    }
+
+    if let Err(e) = result {
+        // The other segments must let go of the file before it is removed.
+        tasks.shutdown().await;
+        let _ = fs::remove_file(&part_path).await;
+        return Err(e);
+    }
+    while let Ok(len) = progress_rx.try_recv() {
+        tracker.advance(len);
+    }
+
+    let mut hasher = Sha256::new();
+    hash_prefix(&part_path, plan.len, &mut hasher).await?;
+    fs::rename(&part_path, path)
+        .await
+        .map_err(|e| format!("File rename error: {}", e))?;
+
+    on_progress(&tracker.snapshot());
+
+    Ok(DownloadedFile {
+        len: plan.len,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+struct Segment {
+    client: Client,
+    url: String,
+    etag: Option<String>,
+    part_path: PathBuf,
+    file_len: u64,
+    range: Range<u64>,
+    throttle: Option<Arc<Throttle>>,
+}
+
+/// Fetches `segment` into the part file, reporting every write through
+/// `progress`, and resumes it under the shared retry policy when the
+/// connection breaks off.
+async fn fetch_segment(
+    segment: Segment,
+    progress: UnboundedSender<u64>,
+) -> Result<(), DownloadError> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(&segment.part_path)
+        .await
+        .map_err(|e| format!("File open error: {}", e))?;
+    file.seek(SeekFrom::Start(segment.range.start))
+        .await
+        .map_err(|e| format!("File write error: {}", e))?;
+
+    let policy = http::retry_policy();
+    let mut pos = segment.range.start;
+    let mut attempt = 1;
+
+    loop {
+        match fetch_remainder(&segment, &mut file, &mut pos, &progress).await {
+            Err(DownloadError::Interrupted(reason)) if policy.allows_retry(attempt) => {
+                let delay = policy.backoff(attempt);
+                println!(
+                    "[WARN] Segment interrupted at byte {}: {} (attempt {}/{}), resuming in {} ms",
+                    pos,
+                    reason,
+                    attempt,
+                    policy.max_attempts,
+                    delay.as_millis()
+                );
+                sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+            Ok(()) => break,
+        }
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| write_failed(e, segment.file_len))?;
+    file.sync_all()
+        .await
+        .map_err(|e| write_failed(e, segment.file_len))
+}
+
+/// Requests the bytes of `segment` from `pos` on and writes them, moving
+/// `pos` past everything written.
+async fn fetch_remainder(
+    segment: &Segment,
+    file: &mut File,
+    pos: &mut u64,
+    progress: &UnboundedSender<u64>,
+) -> Result<(), DownloadError> {
+    let end = segment.range.end;
+    let mut request = segment
+        .client
+        .get(&segment.url)
+        .header(RANGE, format!("bytes={}-{}", pos, end - 1));
+    if let Some(etag) = &segment.etag {
+        request = request.header(IF_RANGE, etag);
+    }
+    let mut resp = http::send(request).await.map_err(request_failed)?;
+
+    let start = header_str(&resp, CONTENT_RANGE).and_then(content_range_start);
+    if resp.status() != StatusCode::PARTIAL_CONTENT || start != Some(*pos) {
+        return Err(DownloadError::Failed(format!(
+            "Server did not send bytes {}-{} (HTTP {})",
+            pos,
+            end - 1,
+            resp.status()
+        )));
+    }
+
+    while *pos < end {
+        let chunk = match http::next_chunk(&mut resp).await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => {
+                return Err(DownloadError::Interrupted(format!(
+                    "Response ended at byte {} of {}",
+                    pos, end
+                )))
+            }
+            Err(e) => return Err(DownloadError::Interrupted(e)),
+        };
+
+        // Anything past the segment belongs to the next one.
+        let len = (chunk.len() as u64).min(end - *pos);
+        if let Some(throttle) = &segment.throttle {
+            throttle.pace(len).await;
+        }
+        file.write_all(&chunk[..len as usize])
+            .await
+            .map_err(|e| write_failed(e, segment.file_len))?;
+        *pos += len;
+        let _ = progress.send(len);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(len: u64, segments: u32) -> Vec<Range<u64>> {
+        RangedFile { len, etag: None }.split(segments).segments
+    }
+
+    #[test]
+    fn splits_into_nearly_equal_ranges_of_a_minimum_length() {
+        const MIB: u64 = MIN_SEGMENT_LEN;
+
+        assert_eq!(
+            split(10 * MIB + 2, 4),
+            vec![
+                0..2621441,
+                2621441..5242882,
+                5242882..7864323,
+                7864323..10 * MIB + 2,
+            ]
+        );
+        assert_eq!(split(3 * MIB, 8).len(), 3);
+        assert_eq!(split(MIB / 2, 4), vec![0..MIB / 2]);
+        assert_eq!(split(8 * MIB, 0), vec![0..8 * MIB]);
+        assert!(split(0, 4).is_empty());
+        assert_eq!(content_range_len("bytes 0-0/10485762"), Some(10 * MIB + 2));
+        assert_eq!(content_range_len("bytes 0-0/*"), None);
+    }
+}
@@ -0,0 +1,39 @@
+//! Capping the transfer rate of a download.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::time::sleep_until;
+
+/// Paces the bytes of one download, all of its connections together, to a
+/// fixed rate.
+///
+/// Every chunk is given a slot of `len / rate` seconds following the one
+/// before, and the reader waits for its slot to end. Unused time is not
+/// saved up, so a pause is never followed by a burst.
+pub(super) struct Throttle {
+    bytes_per_second: u64,
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Throttle {
+            bytes_per_second: bytes_per_second.max(1),
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    /// Waits until `len` more bytes fit into the rate.
+    pub async fn pace(&self, len: u64) {
+        let duration = Duration::from_secs_f64(len as f64 / self.bytes_per_second as f64);
+        let slot_end = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let start = next_slot.filter(|slot| *slot > now).unwrap_or(now);
+            let end = start + duration;
+            *next_slot = Some(end);
+            end
+        };
+        sleep_until(slot_end.into()).await;
+    }
+}
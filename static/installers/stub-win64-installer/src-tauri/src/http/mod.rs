@@ -0,0 +1,500 @@
+//! The HTTP client shared by all installer network traffic.
+//!
+//! The proxy is chosen in this order: `--proxy` on the command line, the
+//! `proxy` config setting, the `HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`
+//! environment variables (honouring `NO_PROXY`), and finally on Windows the
+//! system settings, including PAC scripts and WPAD auto-detection. Extra
+//! root certificates are configured through `tls`, timeouts and the retry
+//! policy through `retry`.
+
+mod retry;
+#[cfg(windows)]
+mod system;
+mod tls;
+
+use crate::{cli, glob};
+use bytes::Bytes;
+use reqwest::redirect::Policy;
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy, RequestBuilder, Response, Url};
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::sync::OnceLock;
+
+pub use retry::{send_with, RetryPolicy, SendError, Timeouts};
+pub use tls::{load_ca_bundle, tls_problem, TlsConfig, CA_BUNDLE_ARG, CA_BUNDLE_ENV_VAR};
+
+pub const PROXY_ARG: &str = "--proxy";
+
+/// Proxy setting that disables every proxy, including the system one.
+pub const DIRECT: &str = "direct";
+
+const PROXY_ENV_VARS: [&str; 6] = [
+    "HTTPS_PROXY",
+    "https_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProxyConfig {
+    /// Proxy for all requests, e.g. `http://proxy.corp:8080`, or `"direct"`.
+    pub url: Option<String>,
+    /// Basic authentication for the proxy, also applied to the system proxy.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Hosts that bypass `url`, comma-separated as in `NO_PROXY`.
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    fn with_auth(&self, proxy: Proxy) -> Proxy {
+        match &self.username {
+            Some(username) => proxy.basic_auth(username, self.password.as_deref().unwrap_or("")),
+            None => proxy,
+        }
+    }
+}
+
+/// Everything the shared clients are built from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpSettings {
+    pub proxy: ProxyConfig,
+    pub tls: TlsConfig,
+    pub timeouts: Timeouts,
+    pub retry: RetryPolicy,
+}
+
+impl HttpSettings {
+    /// Applies the CA bundle from `FLOORP_INSTALLER_CA_BUNDLE`, read
+    /// through `env`, and then `--proxy` and `--ca-bundle` from `args`.
+    pub fn with_overrides(
+        mut self,
+        args: &[String],
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        if let Some(path) = env(CA_BUNDLE_ENV_VAR).filter(|path| !path.trim().is_empty()) {
+            self.tls.ca_bundle = Some(path.into());
+        }
+        if let Some(url) = cli::arg_value(args, PROXY_ARG)? {
+            self.proxy.url = Some(url);
+        }
+        if let Some(path) = cli::arg_value(args, CA_BUNDLE_ARG)? {
+            self.tls.ca_bundle = Some(path.into());
+        }
+        Ok(self)
+    }
+}
+
+/// Where the proxy for a request comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProxySource {
+    Direct,
+    Explicit(String),
+    Environment,
+    System,
+}
+
+impl fmt::Display for ProxySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The explicit URL may carry credentials, so it is not printed.
+        f.write_str(match self {
+            ProxySource::Direct => "no proxy",
+            ProxySource::Explicit(_) => "configured proxy",
+            ProxySource::Environment => "proxy environment variables",
+            ProxySource::System => "system proxy settings",
+        })
+    }
+}
+
+/// Picks the proxy source for `config`, reading variables through `env`.
+pub fn proxy_source(config: &ProxyConfig, env: impl Fn(&str) -> Option<String>) -> ProxySource {
+    match config.url.as_deref().map(str::trim) {
+        Some(url) if url.eq_ignore_ascii_case(DIRECT) => ProxySource::Direct,
+        Some(url) if !url.is_empty() => ProxySource::Explicit(url.to_string()),
+        _ if PROXY_ENV_VARS
+            .iter()
+            .any(|name| env(name).is_some_and(|value| !value.trim().is_empty())) =>
+        {
+            ProxySource::Environment
+        }
+        _ => ProxySource::System,
+    }
+}
+
+/// A client builder that trusts the certificates and uses the proxy
+/// `settings` select.
+pub fn client_builder(settings: &HttpSettings) -> Result<ClientBuilder, String> {
+    settings.timeouts.validate()?;
+    let builder = Client::builder().connect_timeout(settings.timeouts.connect());
+    let builder = settings.tls.apply(builder)?;
+    let config = &settings.proxy;
+
+    match proxy_source(config, |name| env::var(name).ok()) {
+        ProxySource::Direct => Ok(builder.no_proxy()),
+        ProxySource::Explicit(url) => {
+            let proxy = Proxy::all(&url).map_err(|e| format!("Invalid proxy URL: {}", e))?;
+            let proxy = proxy.no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string));
+            Ok(builder.proxy(config.with_auth(proxy)))
+        }
+        // reqwest reads the variables, including NO_PROXY, by itself.
+        ProxySource::Environment => Ok(builder),
+        ProxySource::System => system_proxy(builder, config),
+    }
+}
+
+#[cfg(windows)]
+fn system_proxy(builder: ClientBuilder, config: &ProxyConfig) -> Result<ClientBuilder, String> {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    let settings = match system::ie_proxy_config() {
+        Ok(settings) => settings,
+        Err(e) => {
+            println!("[WARN] {}", e);
+            return Ok(builder);
+        }
+    };
+    let auto_proxy = if settings.uses_auto_proxy() {
+        system::AutoProxy::open()
+    } else {
+        None
+    };
+    if settings.proxy.is_none() && auto_proxy.is_none() {
+        return Ok(builder.no_proxy());
+    }
+
+    // Automatic configuration can take seconds, so it runs once per origin.
+    let cache: Mutex<HashMap<String, Option<String>>> = Mutex::new(HashMap::new());
+    let proxy = Proxy::custom(move |url| {
+        let origin = format!(
+            "{}://{}:{}",
+            url.scheme(),
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        );
+        let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .entry(origin)
+            .or_insert_with(|| {
+                let automatic = auto_proxy
+                    .as_ref()
+                    .and_then(|auto_proxy| auto_proxy.proxy_for_url(url.as_str(), &settings));
+                proxy_for(
+                    url,
+                    automatic,
+                    settings.proxy.as_deref(),
+                    settings.bypass.as_deref(),
+                )
+            })
+            .clone()
+    });
+
+    Ok(builder.proxy(config.with_auth(proxy)))
+}
+
+#[cfg(not(windows))]
+fn system_proxy(builder: ClientBuilder, _config: &ProxyConfig) -> Result<ClientBuilder, String> {
+    Ok(builder)
+}
+
+/// The proxy for `url` under WinINet-style settings. The result of
+/// automatic configuration, when there is one, wins over the manual
+/// `proxy` list, which is skipped for hosts on the `bypass` list.
+pub fn proxy_for(
+    url: &Url,
+    automatic: Option<Option<String>>,
+    proxy: Option<&str>,
+    bypass: Option<&str>,
+) -> Option<String> {
+    let list = match automatic {
+        Some(list) => list?,
+        None => {
+            let host = url.host_str()?;
+            if bypass.is_some_and(|bypass| bypassed(bypass, host)) {
+                return None;
+            }
+            proxy?.to_string()
+        }
+    };
+
+    select_proxy(&list, url.scheme())
+}
+
+/// Picks the entry for `scheme` from a proxy list such as `proxy:8080` or
+/// `http=a:80;https=b:443`, preferring a scheme-specific entry.
+fn select_proxy(list: &str, scheme: &str) -> Option<String> {
+    let entries: Vec<&str> = list
+        .split(|c: char| c == ';' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+
+    let address = entries
+        .iter()
+        .find_map(|entry| {
+            entry
+                .split_once('=')
+                .filter(|(name, _)| name.eq_ignore_ascii_case(scheme))
+                .map(|(_, address)| address)
+        })
+        .or_else(|| entries.iter().copied().find(|entry| !entry.contains('=')))?;
+
+    if address.contains("://") {
+        Some(address.to_string())
+    } else {
+        Some(format!("http://{}", address))
+    }
+}
+
+/// Whether `host` is on a WinINet bypass list, where `<local>` stands for
+/// any host name without a dot.
+fn bypassed(bypass: &str, host: &str) -> bool {
+    bypass
+        .split(|c: char| c == ';' || c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry.eq_ignore_ascii_case("<local>") {
+                !host.contains('.')
+            } else {
+                glob::matches(entry, host)
+            }
+        })
+}
+
+struct SharedClients {
+    client: Client,
+    no_redirect: Client,
+}
+
+static SETTINGS: OnceLock<HttpSettings> = OnceLock::new();
+static SHARED: OnceLock<Result<SharedClients, String>> = OnceLock::new();
+
+/// Sets up the shared clients with `settings`. Only the first call has an
+/// effect; requests made before it use the default settings.
+pub fn init(settings: &HttpSettings) -> Result<(), String> {
+    let _ = SETTINGS.set(settings.clone());
+    shared().map(|_| ())
+}
+
+fn settings() -> &'static HttpSettings {
+    SETTINGS.get_or_init(|| {
+        // Tests provoke failures on purpose; keep their retries quick.
+        if cfg!(test) {
+            HttpSettings {
+                retry: RetryPolicy {
+                    initial_backoff_ms: 10,
+                    max_backoff_ms: 40,
+                    ..RetryPolicy::default()
+                },
+                ..HttpSettings::default()
+            }
+        } else {
+            HttpSettings::default()
+        }
+    })
+}
+
+fn build_shared(settings: &HttpSettings) -> Result<SharedClients, String> {
+    println!(
+        "[INFO] Network requests use {}",
+        proxy_source(&settings.proxy, |name| env::var(name).ok())
+    );
+    let build = |builder: ClientBuilder| {
+        builder
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))
+    };
+
+    Ok(SharedClients {
+        client: build(client_builder(settings)?)?,
+        no_redirect: build(client_builder(settings)?.redirect(Policy::none()))?,
+    })
+}
+
+fn shared() -> Result<&'static SharedClients, String> {
+    SHARED
+        .get_or_init(|| build_shared(settings()))
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+/// The client for all installer requests.
+pub fn client() -> Result<Client, String> {
+    shared().map(|shared| shared.client.clone())
+}
+
+/// Like `client`, but returns redirects instead of following them.
+pub fn no_redirect_client() -> Result<Client, String> {
+    shared().map(|shared| shared.no_redirect.clone())
+}
+
+/// The configured retry policy.
+pub fn retry_policy() -> &'static RetryPolicy {
+    &settings().retry
+}
+
+/// Sends `request` under the configured retry policy and read timeout.
+pub async fn send(request: RequestBuilder) -> Result<Response, SendError> {
+    let settings = settings();
+    send_with(request, &settings.retry, settings.timeouts.read()).await
+}
+
+/// Like `send`, but the whole exchange, reading the body included, has to
+/// finish within the total timeout. For requests whose body is read at once.
+pub async fn send_bounded(request: RequestBuilder) -> Result<Response, SendError> {
+    send(request.timeout(settings().timeouts.total())).await
+}
+
+/// Reads the next chunk of `resp`'s body, failing if none arrives within
+/// the configured read timeout.
+pub async fn next_chunk(resp: &mut Response) -> Result<Option<Bytes>, String> {
+    let read_timeout = settings().timeouts.read();
+    match tokio::time::timeout(read_timeout, resp.chunk()).await {
+        Ok(chunk) => chunk.map_err(|e| format!("Response read error: {}", e)),
+        Err(_) => Err(format!("No data received for {:?}", read_timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Response, TestServer};
+    use std::path::PathBuf;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn with_url(url: &str) -> ProxyConfig {
+        ProxyConfig {
+            url: Some(url.to_string()),
+            ..ProxyConfig::default()
+        }
+    }
+
+    #[test]
+    fn prefers_explicit_then_environment_then_system_proxy() {
+        let no_env = |_: &str| None;
+        let env = |name: &str| (name == "https_proxy").then(|| "http://env:3128".to_string());
+
+        assert_eq!(
+            proxy_source(&with_url("http://proxy:8080"), env),
+            ProxySource::Explicit("http://proxy:8080".to_string())
+        );
+        assert_eq!(proxy_source(&with_url("DIRECT"), env), ProxySource::Direct);
+        assert_eq!(
+            proxy_source(&ProxyConfig::default(), env),
+            ProxySource::Environment
+        );
+        assert_eq!(
+            proxy_source(&ProxyConfig::default(), no_env),
+            ProxySource::System
+        );
+    }
+
+    #[test]
+    fn command_line_overrides_environment_and_config() {
+        let config = HttpSettings {
+            proxy: with_url("http://config:8080"),
+            ..HttpSettings::default()
+        };
+        let no_env = |_: &str| None;
+        let env = |name: &str| (name == CA_BUNDLE_ENV_VAR).then(|| "env.pem".to_string());
+
+        assert_eq!(
+            config
+                .clone()
+                .with_overrides(&args(&["--minimized"]), no_env),
+            Ok(config.clone())
+        );
+
+        let overridden = config
+            .clone()
+            .with_overrides(&args(&["--proxy", "http://cli:3128"]), env)
+            .unwrap();
+        assert_eq!(overridden.proxy, with_url("http://cli:3128"));
+        assert_eq!(overridden.tls.ca_bundle, Some(PathBuf::from("env.pem")));
+
+        let overridden = config
+            .clone()
+            .with_overrides(&args(&["--proxy=direct", "--ca-bundle=cli.pem"]), env)
+            .unwrap();
+        assert_eq!(overridden.proxy, with_url("direct"));
+        assert_eq!(overridden.tls.ca_bundle, Some(PathBuf::from("cli.pem")));
+
+        assert!(config.with_overrides(&args(&["--proxy"]), no_env).is_err());
+    }
+
+    #[test]
+    fn follows_wininet_proxy_lists_and_bypass_rules() {
+        let https = Url::parse("https://github.com/").unwrap();
+        let intranet = Url::parse("http://intranet/").unwrap();
+        let lists = Some("http=web:80;https=secure:443");
+        let bypass = Some("<local>;*.corp.example");
+
+        assert_eq!(
+            proxy_for(&https, None, lists, bypass),
+            Some("http://secure:443".to_string())
+        );
+        assert_eq!(
+            proxy_for(&https, None, Some("proxy:8080"), bypass),
+            Some("http://proxy:8080".to_string())
+        );
+        assert_eq!(proxy_for(&intranet, None, lists, bypass), None);
+        assert_eq!(
+            proxy_for(
+                &Url::parse("https://files.CORP.example/").unwrap(),
+                None,
+                lists,
+                bypass
+            ),
+            None
+        );
+        assert_eq!(
+            proxy_for(
+                &https,
+                Some(Some("pac:3128;backup:3128".to_string())),
+                lists,
+                bypass
+            ),
+            Some("http://pac:3128".to_string())
+        );
+        assert_eq!(proxy_for(&https, Some(None), lists, bypass), None);
+        assert_eq!(proxy_for(&https, None, Some("ftp=old:21"), None), None);
+    }
+
+    #[tokio::test]
+    async fn sends_requests_through_explicit_proxy_with_credentials() {
+        let proxy = TestServer::start(|req| {
+            if req.path != "http://floorp.invalid/installer.exe" {
+                return Response::status(404);
+            }
+            match req.header("Proxy-Authorization") {
+                Some("Basic dXNlcjpzZWNyZXQ=") => Response::ok("via proxy"),
+                _ => Response::status(407),
+            }
+        });
+        let settings = HttpSettings {
+            proxy: ProxyConfig {
+                url: Some(proxy.url("")),
+                username: Some("user".to_string()),
+                password: Some("secret".to_string()),
+                no_proxy: Some("localhost".to_string()),
+            },
+            ..HttpSettings::default()
+        };
+        let client = client_builder(&settings).unwrap().build().unwrap();
+
+        let resp = client
+            .get("http://floorp.invalid/installer.exe")
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().await.unwrap(), "via proxy");
+    }
+}
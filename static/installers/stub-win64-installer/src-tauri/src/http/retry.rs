@@ -0,0 +1,358 @@
+//! Timeouts and retrying transient failures with jittered exponential
+//! backoff.
+
+use super::tls::tls_problem;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Timeouts {
+    /// Seconds to establish a connection, including the TLS handshake.
+    pub connect_secs: u64,
+    /// Seconds to wait for a response or for more data of its body.
+    pub read_secs: u64,
+    /// Seconds a request for metadata may take, body included. Downloads
+    /// are not bound by it, as a rate cap can stretch them out at will;
+    /// their body only has to keep arriving within the read timeout.
+    pub total_secs: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            connect_secs: 15,
+            read_secs: 30,
+            total_secs: 3600,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn connect(&self) -> Duration {
+        Duration::from_secs(self.connect_secs)
+    }
+
+    pub fn read(&self) -> Duration {
+        Duration::from_secs(self.read_secs)
+    }
+
+    pub fn total(&self) -> Duration {
+        Duration::from_secs(self.total_secs)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.connect_secs == 0 || self.read_secs == 0 || self.total_secs == 0 {
+            return Err("timeouts must be at least one second".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// Attempts per request, the first one included.
+    pub max_attempts: u32,
+    /// Delay before the first retry; it doubles with every further one.
+    pub initial_backoff_ms: u64,
+    /// Upper bound of the delay between two attempts.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether another attempt may follow attempt number `attempt`.
+    pub fn allows_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    /// How long to wait after attempt number `attempt` failed.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        backoff_with_jitter(self, attempt, random_unit())
+    }
+}
+
+/// The delay after attempt number `attempt` (from 1): the exponential
+/// delay, capped, of which the upper half is scaled by `jitter` in `[0, 1)`
+/// so that clients that failed together do not retry together.
+fn backoff_with_jitter(policy: &RetryPolicy, attempt: u32, jitter: f64) -> Duration {
+    let exponential = policy
+        .initial_backoff_ms
+        .saturating_mul(1u64 << attempt.saturating_sub(1).min(32));
+    let capped = exponential.min(policy.max_backoff_ms);
+    let half = capped / 2;
+
+    Duration::from_millis(half + (half as f64 * jitter) as u64)
+}
+
+/// A number in `[0, 1)`. Jitter needs no more than the randomly keyed
+/// hasher the standard library already provides.
+fn random_unit() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// A request that could not be completed.
+#[derive(Debug)]
+pub enum SendError {
+    Request(reqwest::Error),
+    /// No response arrived within the read timeout.
+    Stalled {
+        url: String,
+        after: Duration,
+    },
+}
+
+impl SendError {
+    /// The certificate or TLS problem behind the failure, if it is one.
+    pub fn tls_problem(&self) -> Option<String> {
+        match self {
+            SendError::Request(e) => tls_problem(e),
+            SendError::Stalled { .. } => None,
+        }
+    }
+
+    fn is_transient(&self) -> bool {
+        match self {
+            SendError::Request(e) => {
+                (e.is_timeout() || e.is_connect() || e.is_request()) && tls_problem(e).is_none()
+            }
+            SendError::Stalled { .. } => true,
+        }
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendError::Request(e) => e.fmt(f),
+            SendError::Stalled { url, after } => {
+                write!(f, "no response from {} within {:?}", url, after)
+            }
+        }
+    }
+}
+
+impl Error for SendError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SendError::Request(e) => Some(e),
+            SendError::Stalled { .. } => None,
+        }
+    }
+}
+
+/// Whether a response with `status` is worth asking for again.
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Sends `request`, retrying connection failures, stalls and server
+/// errors under `policy`. After the last attempt a server error is
+/// returned as the response, for the caller to report.
+pub async fn send_with(
+    request: RequestBuilder,
+    policy: &RetryPolicy,
+    read_timeout: Duration,
+) -> Result<Response, SendError> {
+    let (client, request) = request.build_split();
+    let mut request = request.map_err(SendError::Request)?;
+    let mut attempt = 1;
+
+    loop {
+        // A request with a streaming body cannot be cloned, and is sent once.
+        let retry = if policy.allows_retry(attempt) {
+            request.try_clone()
+        } else {
+            None
+        };
+        let url = request.url().to_string();
+
+        let result = match timeout(read_timeout, client.execute(request)).await {
+            Ok(result) => result.map_err(SendError::Request),
+            Err(_) => Err(SendError::Stalled {
+                url: url.clone(),
+                after: read_timeout,
+            }),
+        };
+        let (next, failure) = match (result, retry) {
+            (Ok(resp), Some(next)) if is_transient_status(resp.status()) => {
+                (next, format!("{} returned HTTP {}", url, resp.status()))
+            }
+            (Err(error), Some(next)) if error.is_transient() => (next, error.to_string()),
+            (result, _) => return result,
+        };
+
+        let delay = policy.backoff(attempt);
+        println!(
+            "[WARN] {} (attempt {}/{}), retrying in {} ms",
+            failure,
+            attempt,
+            policy.max_attempts,
+            delay.as_millis()
+        );
+        sleep(delay).await;
+
+        request = next;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Response, TestServer};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 40,
+        }
+    }
+
+    /// Answers with `failure` for the first `failures` requests.
+    fn flaky_server(failures: u32, failure: u16) -> (TestServer, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&requests);
+        let server = TestServer::start(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                Response::status(failure)
+            } else {
+                Response::ok("installer")
+            }
+        });
+        (server, requests)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_with_jitter() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+        };
+        let delay = |attempt, jitter| backoff_with_jitter(&policy, attempt, jitter).as_millis();
+
+        assert_eq!(delay(1, 0.0), 50);
+        assert_eq!(delay(1, 0.99), 99);
+        assert_eq!(delay(3, 0.0), 200);
+        assert_eq!(delay(5, 0.0), 500);
+        assert_eq!(delay(64, 0.5), 750);
+        assert!((0..100).all(|_| (0.0..1.0).contains(&random_unit())));
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_until_success() {
+        let (server, requests) = flaky_server(2, 503);
+        let client = reqwest::Client::new();
+
+        let resp = send_with(client.get(server.url("/")), &fast_policy(4), READ_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), 200);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn returns_the_last_server_error_after_max_attempts() {
+        let (server, requests) = flaky_server(u32::MAX, 500);
+        let client = reqwest::Client::new();
+
+        let resp = send_with(client.get(server.url("/")), &fast_policy(3), READ_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), 500);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (server, requests) = flaky_server(u32::MAX, 404);
+        let client = reqwest::Client::new();
+
+        let resp = send_with(client.get(server.url("/")), &fast_policy(3), READ_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), 404);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_stalled_responses() {
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&requests);
+        let server = TestServer::start(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                thread::sleep(Duration::from_millis(500));
+            }
+            Response::ok("installer")
+        });
+        let client = reqwest::Client::new();
+        let read_timeout = Duration::from_millis(100);
+
+        let resp = send_with(client.get(server.url("/")), &fast_policy(2), read_timeout)
+            .await
+            .unwrap();
+        assert_eq!(resp.text().await.unwrap(), "installer");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        requests.store(0, Ordering::SeqCst);
+        let err = send_with(client.get(server.url("/")), &fast_policy(1), read_timeout)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SendError::Stalled { .. }), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn retries_refused_connections() {
+        let client = reqwest::Client::new();
+        let started = std::time::Instant::now();
+
+        let err = send_with(
+            client.get("http://127.0.0.1:1/"),
+            &fast_policy(3),
+            READ_TIMEOUT,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(
+            matches!(err, SendError::Request(ref e) if e.is_connect()),
+            "{}",
+            err
+        );
+        // Two backoff delays of at least 5 and 10 ms lie between the attempts.
+        assert!(started.elapsed() >= Duration::from_millis(15));
+    }
+}
@@ -0,0 +1,140 @@
+//! The current user's WinINet ("Internet Options") proxy settings, read
+//! through WinHTTP so that PAC scripts and WPAD auto-detection work too.
+
+use std::ffi::c_void;
+use std::iter::once;
+use windows::core::{w, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{GlobalFree, HGLOBAL, TRUE};
+use windows::Win32::Networking::WinHttp::{
+    WinHttpCloseHandle, WinHttpGetIEProxyConfigForCurrentUser, WinHttpGetProxyForUrl, WinHttpOpen,
+    WINHTTP_ACCESS_TYPE_NAMED_PROXY, WINHTTP_ACCESS_TYPE_NO_PROXY, WINHTTP_AUTOPROXY_AUTO_DETECT,
+    WINHTTP_AUTOPROXY_CONFIG_URL, WINHTTP_AUTOPROXY_OPTIONS, WINHTTP_AUTO_DETECT_TYPE_DHCP,
+    WINHTTP_AUTO_DETECT_TYPE_DNS_A, WINHTTP_CURRENT_USER_IE_PROXY_CONFIG, WINHTTP_PROXY_INFO,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct IeProxyConfig {
+    /// "Automatically detect settings" (WPAD).
+    pub auto_detect: bool,
+    /// "Use automatic configuration script" (PAC).
+    pub auto_config_url: Option<String>,
+    /// The manual proxy list, e.g. `proxy:8080` or `http=a:80;https=b:443`.
+    pub proxy: Option<String>,
+    /// Hosts that bypass the manual proxy, separated by `;`.
+    pub bypass: Option<String>,
+}
+
+impl IeProxyConfig {
+    pub fn uses_auto_proxy(&self) -> bool {
+        self.auto_detect || self.auto_config_url.is_some()
+    }
+}
+
+pub fn ie_proxy_config() -> Result<IeProxyConfig, String> {
+    let mut raw = WINHTTP_CURRENT_USER_IE_PROXY_CONFIG::default();
+    unsafe { WinHttpGetIEProxyConfigForCurrentUser(&mut raw) }
+        .map_err(|e| format!("Failed to read system proxy settings: {}", e))?;
+
+    Ok(IeProxyConfig {
+        auto_detect: raw.fAutoDetect.as_bool(),
+        auto_config_url: take_string(raw.lpszAutoConfigUrl),
+        proxy: take_string(raw.lpszProxy),
+        bypass: take_string(raw.lpszProxyBypass),
+    })
+}
+
+/// A WinHTTP session used only to evaluate WPAD and PAC scripts.
+pub struct AutoProxy {
+    session: *mut c_void,
+}
+
+// The session handle is only passed to WinHttpGetProxyForUrl, which may be
+// called from any thread.
+unsafe impl Send for AutoProxy {}
+unsafe impl Sync for AutoProxy {}
+
+impl AutoProxy {
+    pub fn open() -> Option<Self> {
+        let session = unsafe {
+            WinHttpOpen(
+                w!("Floorp-Installer"),
+                WINHTTP_ACCESS_TYPE_NO_PROXY,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                0,
+            )
+        };
+        if session.is_null() {
+            println!("[WARN] WinHttpOpen failed, ignoring automatic proxy configuration");
+            return None;
+        }
+        Some(AutoProxy { session })
+    }
+
+    /// Runs automatic proxy configuration for `url`. Returns `None` if it
+    /// failed, `Some(None)` if the URL is to be fetched directly and
+    /// otherwise the proxy list.
+    pub fn proxy_for_url(&self, url: &str, config: &IeProxyConfig) -> Option<Option<String>> {
+        let url_wide: Vec<u16> = url.encode_utf16().chain(once(0)).collect();
+        let config_url_wide: Option<Vec<u16>> = config
+            .auto_config_url
+            .as_ref()
+            .map(|config_url| config_url.encode_utf16().chain(once(0)).collect());
+
+        let mut options = WINHTTP_AUTOPROXY_OPTIONS {
+            fAutoLogonIfChallenged: TRUE,
+            ..Default::default()
+        };
+        if config.auto_detect {
+            options.dwFlags |= WINHTTP_AUTOPROXY_AUTO_DETECT;
+            options.dwAutoDetectFlags =
+                WINHTTP_AUTO_DETECT_TYPE_DHCP | WINHTTP_AUTO_DETECT_TYPE_DNS_A;
+        }
+        if let Some(config_url_wide) = &config_url_wide {
+            options.dwFlags |= WINHTTP_AUTOPROXY_CONFIG_URL;
+            options.lpszAutoConfigUrl = PCWSTR(config_url_wide.as_ptr());
+        }
+
+        let mut info = WINHTTP_PROXY_INFO::default();
+        let result = unsafe {
+            WinHttpGetProxyForUrl(
+                self.session,
+                PCWSTR(url_wide.as_ptr()),
+                &mut options,
+                &mut info,
+            )
+        };
+        let proxy = take_string(info.lpszProxy);
+        let _ = take_string(info.lpszProxyBypass);
+
+        match result {
+            Ok(()) if info.dwAccessType == WINHTTP_ACCESS_TYPE_NAMED_PROXY => Some(proxy),
+            Ok(()) => Some(None),
+            Err(e) => {
+                println!(
+                    "[WARN] Automatic proxy configuration failed for {}: {}",
+                    url, e
+                );
+                None
+            }
+        }
+    }
+}
+
+impl Drop for AutoProxy {
+    fn drop(&mut self) {
+        let _ = unsafe { WinHttpCloseHandle(self.session) };
+    }
+}
+
+/// Copies a string WinHTTP allocated for the caller and frees it.
+fn take_string(value: PWSTR) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    let copy = unsafe { value.to_string() }.ok();
+    // GlobalFree returns NULL on success, which the binding reports as an
+    // error, so its result is not meaningful.
+    let _ = unsafe { GlobalFree(HGLOBAL(value.0 as *mut c_void)) };
+    copy.filter(|copy| !copy.trim().is_empty())
+}
@@ -0,0 +1,206 @@
+//! Trust settings for networks that intercept TLS, and naming the
+//! certificate problem when a connection fails.
+
+use reqwest::{Certificate, ClientBuilder};
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CA_BUNDLE_ARG: &str = "--ca-bundle";
+pub const CA_BUNDLE_ENV_VAR: &str = "FLOORP_INSTALLER_CA_BUNDLE";
+
+/// Schannel and CryptoAPI status codes, as reported in `os error` messages,
+/// that point at the certificate rather than the network.
+const CERTIFICATE_STATUS_CODES: [(i32, &str); 7] = [
+    (
+        -2146893019,
+        "the certificate chain was issued by an untrusted authority",
+    ),
+    (
+        -2146762487,
+        "the certificate chain ends in an untrusted root",
+    ),
+    (
+        -2146893022,
+        "the certificate name does not match the server",
+    ),
+    (-2146893016, "the certificate has expired"),
+    (-2146893017, "the certificate was not accepted"),
+    (
+        -2146885614,
+        "the certificate revocation status could not be checked",
+    ),
+    (-2146885613, "the certificate revocation server is offline"),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// PEM file with extra root certificates, such as the CA of a proxy
+    /// that inspects TLS traffic.
+    pub ca_bundle: Option<PathBuf>,
+    /// Whether the operating system's certificate store is trusted. When
+    /// it is not, only `ca_bundle` is.
+    pub system_certificates: bool,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            ca_bundle: None,
+            system_certificates: true,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub(super) fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, String> {
+        let mut builder = builder.tls_built_in_root_certs(self.system_certificates);
+
+        match &self.ca_bundle {
+            Some(path) => {
+                let certificates = load_ca_bundle(path)?;
+                println!(
+                    "[INFO] Trusting {} certificate(s) from {}",
+                    certificates.len(),
+                    path.display()
+                );
+                for certificate in certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+            }
+            None if !self.system_certificates => {
+                return Err("systemCertificates is off, but no CA bundle is set".to_string())
+            }
+            None => {}
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Reads every certificate in the PEM file at `path`.
+pub fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>, String> {
+    let pem = fs::read(path)
+        .map_err(|e| format!("Failed to read CA bundle {}: {}", path.display(), e))?;
+    let certificates = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("Invalid CA bundle {}: {}", path.display(), e))?;
+
+    if certificates.is_empty() {
+        return Err(format!(
+            "CA bundle {} contains no certificates",
+            path.display()
+        ));
+    }
+    Ok(certificates)
+}
+
+/// Describes the certificate or TLS problem behind `err`, or returns `None`
+/// if the request failed for another reason. Typically a proxy that
+/// inspects traffic presents a certificate that is not trusted.
+pub fn tls_problem(err: &reqwest::Error) -> Option<String> {
+    let mut is_tls = false;
+    let mut messages = Vec::new();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        is_tls |= cause.is::<native_tls::Error>();
+        messages.push(cause.to_string());
+        source = cause.source();
+    }
+
+    if is_tls {
+        Some(describe_tls_problem(&messages))
+    } else {
+        None
+    }
+}
+
+/// Names the problem in the messages of a TLS error chain, outermost
+/// first: a known certificate status if there is one, otherwise the
+/// innermost message.
+fn describe_tls_problem(messages: &[String]) -> String {
+    for (code, problem) in CERTIFICATE_STATUS_CODES {
+        let marker = format!("os error {}", code);
+        if messages.iter().any(|message| message.contains(&marker)) {
+            return problem.to_string();
+        }
+    }
+
+    messages.last().cloned().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Response, TestServer};
+
+    const TEST_ROOT: &str = "-----BEGIN CERTIFICATE-----
+MIIBjDCCATOgAwIBAgIUc/jiXCfR7iV8XEJ+UEpUQ60Bz8QwCgYIKoZIzj0EAwIw
+GzEZMBcGA1UEAwwQRmxvb3JwIFRlc3QgUm9vdDAgFw0yNjEwMTgwNzMwNDdaGA8y
+MTI2MDkyNDA3MzA0N1owGzEZMBcGA1UEAwwQRmxvb3JwIFRlc3QgUm9vdDBZMBMG
+ByqGSM49AgEGCCqGSM49AwEHA0IABJGXRuc09ZdicHn1Pz+TxT+rVce66KrA2dvd
+w/KTRX9svAkteaFvfix3h0YCmyj+bgRMUiXh7Ec2u8PeIu0i24SjUzBRMB0GA1Ud
+DgQWBBTujKisAUfdiNGQYglbYm2TetUdMzAfBgNVHSMEGDAWgBTujKisAUfdiNGQ
+YglbYm2TetUdMzAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0cAMEQCICRQ
+ktGib4VOFvzKCPdo5sv8beHoLP89ZSKRZZd+pYjOAiBYR13E+mErzYkr49tZcs8W
+KsDdFkq/MSBGGC9JhRRUNg==
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn loads_every_certificate_in_a_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("corp-ca.pem");
+        std::fs::write(&bundle, format!("{}\n{}", TEST_ROOT, TEST_ROOT)).unwrap();
+        let empty = dir.path().join("empty.pem");
+        std::fs::write(&empty, "not a certificate\n").unwrap();
+
+        assert_eq!(load_ca_bundle(&bundle).unwrap().len(), 2);
+        assert!(load_ca_bundle(&empty).is_err());
+        assert!(load_ca_bundle(&dir.path().join("missing.pem")).is_err());
+        assert!(TlsConfig {
+            ca_bundle: None,
+            system_certificates: false,
+        }
+        .apply(reqwest::Client::builder())
+        .is_err());
+    }
+
+    #[test]
+    fn names_certificate_problems() {
+        let chain = |inner: &str| vec!["error trying to connect".to_string(), inner.to_string()];
+
+        assert_eq!(
+            describe_tls_problem(&chain(
+                "The certificate chain was issued by an authority that is not trusted. \
+                 (os error -2146893019)"
+            )),
+            "the certificate chain was issued by an untrusted authority"
+        );
+        assert_eq!(
+            describe_tls_problem(&chain(
+                "error:0A000086:SSL routines::certificate verify failed"
+            )),
+            "error:0A000086:SSL routines::certificate verify failed"
+        );
+    }
+
+    #[tokio::test]
+    async fn tells_tls_failures_from_other_errors() {
+        // A plain HTTP server cannot complete a TLS handshake.
+        let server = TestServer::start(|_| Response::ok("not TLS"));
+        let url = server.url("/").replace("http://", "https://");
+
+        let err = reqwest::Client::new().get(&url).send().await.unwrap_err();
+
+        assert!(tls_problem(&err).is_some(), "{:?}", err);
+
+        let refused = reqwest::Client::new()
+            .get("https://127.0.0.1:1/")
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(tls_problem(&refused), None);
+    }
+}
@@ -1,3 +1,22 @@
+pub mod arch;
+pub mod cache;
+pub mod cancel;
+pub mod checksum;
+pub mod config;
+pub mod download;
+pub mod http;
+pub mod offline;
+pub mod release;
+pub mod signing;
+pub mod workspace;
+
+mod cli;
+mod date;
+mod glob;
+
+#[cfg(test)]
+mod test_support;
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
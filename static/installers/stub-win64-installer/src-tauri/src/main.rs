@@ -1,34 +1,47 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use reqwest::Client;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{
-    ffi::{c_void, OsStr},
-    mem::transmute,
+    ffi::OsStr,
     os::windows::ffi::OsStrExt,
     ptr::null_mut,
     fs,
     io::{self, Read, Write},
 };
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use stub_installer_lib::arch;
+use stub_installer_lib::cache::InstallerCache;
+use stub_installer_lib::cancel::{
+    CancelOutcome, CancelToken, Cancelled, InstallControl, InstallPhase, CANCELLED_ERROR,
+};
+use stub_installer_lib::config::{self, InstallerConfig};
+use stub_installer_lib::download::{
+    self, DownloadError, DownloadOptions, DownloadProgress, PROGRESS_EVENT,
+};
+use stub_installer_lib::http::{self, HttpSettings};
+use stub_installer_lib::offline::{self, LocalInstaller};
+use stub_installer_lib::release::{
+    self, AssetSelector, ReleaseChannel, ReleaseSummary, ResolveError, SourceFailure,
+};
+use stub_installer_lib::signing::{
+    self, RevocationCheck, Rule, SignatureError, SignatureReport, SignatureReports,
+    TrustPolicy,
+};
+use stub_installer_lib::workspace::Workspace;
 use tokio::process::Command;
 use tokio::time::{sleep, Duration};
-use tauri::Manager;
+use tauri::{AppHandle, Emitter, Manager, State};
 use windows::{
-    core::{PCWSTR, PWSTR},
-    Win32::Foundation::{GetLastError, HANDLE, HWND},
-    Win32::Security::Cryptography::{
-        CertGetNameStringW, CryptQueryObject, CERT_NAME_SIMPLE_DISPLAY_TYPE,
-        CERT_QUERY_CONTENT_FLAG_PKCS7_SIGNED_EMBED, CERT_QUERY_CONTENT_TYPE,
-        CERT_QUERY_ENCODING_TYPE, CERT_QUERY_FORMAT_FLAG_BINARY, CERT_QUERY_FORMAT_TYPE,
-        CERT_QUERY_OBJECT_FILE, HCERTSTORE,
+    core::{HRESULT, PCWSTR, PWSTR},
+    Win32::Foundation::{
+        GetLastError, CERT_E_REVOCATION_FAILURE, CERT_E_REVOKED, CRYPT_E_NO_REVOCATION_CHECK,
+        CRYPT_E_REVOCATION_OFFLINE, CRYPT_E_REVOKED, HANDLE, HWND, TRUST_E_TIME_STAMP,
     },
     Win32::Security::WinTrust::{
         WinVerifyTrust, WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WINTRUST_DATA_0,
-        WINTRUST_DATA_PROVIDER_FLAGS, WINTRUST_DATA_UICONTEXT, WINTRUST_FILE_INFO, WTD_CHOICE_FILE,
-        WTD_REVOKE_NONE, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
+        WINTRUST_DATA_UICONTEXT, WINTRUST_FILE_INFO, WTD_CACHE_ONLY_URL_RETRIEVAL,
+        WTD_CHOICE_FILE, WTD_REVOCATION_CHECK_CHAIN, WTD_REVOCATION_CHECK_NONE, WTD_REVOKE_NONE,
+        WTD_REVOKE_WHOLECHAIN, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
     },
     Win32::System::Registry::{
         RegOpenKeyExW, RegQueryValueExW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ,
@@ -38,29 +51,27 @@ use windows::{
     },
 };
 
-const EXPECTED_SIGNERS: [&str; 2] = [
-    "SignPath Foundation",
-    "Open Source Developer, Ryosuke Asano",
-];
+/// Start of the error returned when the WebView2 Runtime bootstrapper is
+/// rejected.
+const WEBVIEW2_SIGNATURE_ERROR: &str =
+    "The WebView2 Runtime installer is not signed by Microsoft and was not run";
 
-/// Check if the CPU supports SSE4.1 instructions
-fn check_sse4_1_support() -> bool {
-    #[cfg(target_arch = "x86_64")]
-    {
-        // Use is_x86_feature_detected! macro which is safer and handles LLVM constraints
-        let sse4_1_supported = is_x86_feature_detected!("sse4.1");
+/// The features the Floorp build for the native architecture requires that
+/// the CPU lacks, such as SSE4.1 on x86_64 or NEON on ARM64.
+fn missing_cpu_features() -> Vec<String> {
+    let native = arch::native_arch();
+    println!("[INFO] Native machine architecture: {}", native);
 
-        println!("[INFO] CPUID check - SSE4.1 support: {}", sse4_1_supported);
+    feature_names(&arch::missing_features(native, arch::detect_feature))
+}
 
-        sse4_1_supported
-    }
+/// CPU features the way users know them, such as `SSE4.1`.
+fn feature_names(features: &[&str]) -> Vec<String> {
+    features.iter().map(|feature| feature.to_uppercase()).collect()
+}
 
-    #[cfg(not(target_arch = "x86_64"))]
-    {
-        // For non-x86_64 architectures, assume support
-        println!("[INFO] Non-x86_64 architecture detected, assuming SSE4.1 support");
-        true
-    }
+fn asset_selector(config: &InstallerConfig) -> AssetSelector {
+    AssetSelector::new(arch::native_arch(), config.asset_patterns.clone())
 }
 
 /// Check available disk space (in MB) for the given path
@@ -94,6 +105,45 @@ fn get_available_disk_space(path: &str) -> Result<u64, String> {
     }
 }
 
+/// The drive `path` is on, e.g. `C:`.
+fn drive_of(path: &Path) -> String {
+    match path.components().next() {
+        Some(std::path::Component::Prefix(prefix)) => {
+            prefix.as_os_str().to_string_lossy().into_owned()
+        }
+        _ => path.display().to_string(),
+    }
+}
+
+/// Error key for a drive without room for `needed` more bytes.
+fn disk_full_error(path: &Path, needed: u64) -> String {
+    format!(
+        "rust.errors.disk_full|{}|{}",
+        drive_of(path),
+        needed.div_ceil(1024 * 1024)
+    )
+}
+
+/// Fails unless the drive holding `dir` has room for `needed` more bytes.
+fn check_download_space(dir: &Path, needed: u64) -> Result<(), String> {
+    match get_available_disk_space(&dir.to_string_lossy()) {
+        Ok(available_mb) if available_mb.saturating_mul(1024 * 1024) < needed => {
+            println!(
+                "[ERROR] Not enough space for the download on {}: {} MB available, {} bytes needed",
+                drive_of(dir),
+                available_mb,
+                needed
+            );
+            Err(disk_full_error(dir, needed))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            println!("[WARN] Could not check space for the download: {}", e);
+            Ok(())
+        }
+    }
+}
+
 /// Check if there's enough disk space (minimum 300MB) for installation
 fn check_disk_space_requirements(install_path: Option<&str>) -> Result<bool, String> {
     const MINIMUM_SPACE_MB: u64 = 300;
@@ -156,37 +206,15 @@ fn check_disk_space_requirements(install_path: Option<&str>) -> Result<bool, Str
 fn check_cpu_requirements() {
     println!("[INFO] Checking CPU requirements...");
 
-    if !check_sse4_1_support() {
-        println!("[ERROR] CPU does not support SSE4.1 instructions");
-        println!("[ERROR] Floorp requires a processor with SSE4.1 support");
+    let missing = missing_cpu_features();
+    if !missing.is_empty() {
+        let missing = missing.join(", ");
+        println!("[ERROR] CPU does not support {}", missing);
         println!("[ERROR] This device is not supported");
 
         // Show error dialog and exit
-        let error_message = "Floorp requires a processor with SSE4.1 support.\n\nThis device is not supported.\n\nPlease use a device with a compatible processor.";
-
-        // Try to show a Windows message box
-        unsafe {
-            use windows::Win32::UI::WindowsAndMessaging::{
-                MessageBoxW, MB_OK, MB_ICONERROR
-            };
-
-            let wide_message: Vec<u16> = OsStr::new(error_message)
-                .encode_wide()
-                .chain(std::iter::once(0))
-                .collect();
-
-            let wide_title: Vec<u16> = OsStr::new("Floorp Installer - Unsupported Device")
-                .encode_wide()
-                .chain(std::iter::once(0))
-                .collect();
-
-            let _ = MessageBoxW(
-                HWND::default(),
-                PCWSTR(wide_message.as_ptr()),
-                PCWSTR(wide_title.as_ptr()),
-                MB_OK | MB_ICONERROR,
-            );
-        }
+        let error_message = format!("Floorp requires a processor with {} support.\n\nThis device is not supported.\n\nPlease use a device with a compatible processor.", missing);
+        show_error_dialog("Floorp Installer - Unsupported Device", &error_message);
 
         std::process::exit(1);
     }
@@ -194,9 +222,25 @@ fn check_cpu_requirements() {
     println!("[INFO] CPU requirements met");
 }
 
+/// Shows a Windows message box, for errors that come before the window can.
+fn show_error_dialog(title: &str, message: &str) {
+    use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+    let wide_message = wide_null(message);
+    let wide_title = wide_null(title);
+    unsafe {
+        let _ = MessageBoxW(
+            HWND::default(),
+            PCWSTR(wide_message.as_ptr()),
+            PCWSTR(wide_title.as_ptr()),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+}
+
 #[tauri::command]
-async fn check_cpu_support() -> Result<bool, String> {
-    Ok(check_sse4_1_support())
+async fn check_cpu_support() -> Result<Vec<String>, String> {
+    Ok(missing_cpu_features())
 }
 
 #[tauri::command]
@@ -209,8 +253,73 @@ async fn check_disk_space(custom_install_path: Option<String>) -> Result<bool, S
 
 #[tauri::command]
 async fn download_and_run_installer(
+    app: AppHandle,
+    config: State<'_, InstallerConfig>,
+    control: State<'_, InstallControl>,
+    reports: State<'_, SignatureReports>,
     use_admin: bool,
     custom_install_path: Option<String>,
+    channel: Option<ReleaseChannel>,
+    max_bytes_per_second: Option<u64>,
+) -> Result<String, String> {
+    let cancel = control.start();
+    reports.clear();
+    let mut download_options = config.download.clone();
+    if let Some(rate) = max_bytes_per_second {
+        download_options.max_bytes_per_second = Some(rate).filter(|rate| *rate > 0);
+    }
+    let result = install_floorp(
+        &app,
+        &config,
+        &download_options,
+        &control,
+        &reports,
+        &cancel,
+        use_admin,
+        custom_install_path,
+        channel,
+    )
+    .await;
+    control.finish();
+    result
+}
+
+#[tauri::command]
+async fn cancel_install(
+    control: State<'_, InstallControl>,
+    confirmed: bool,
+) -> Result<CancelOutcome, String> {
+    let outcome = control.cancel(confirmed);
+    println!("[INFO] Cancel requested: {:?}", outcome);
+    Ok(outcome)
+}
+
+/// What the signature check of the last install found, for the details of
+/// the error screen.
+#[tauri::command]
+async fn get_signature_report(
+    reports: State<'_, SignatureReports>,
+) -> Result<Option<SignatureReport>, String> {
+    Ok(reports.last())
+}
+
+/// Removes what a cancelled install left behind and returns the error key.
+async fn cancelled_install(path: &Path) -> String {
+    println!("[INFO] Installation cancelled");
+    download::discard(path).await;
+    CANCELLED_ERROR.to_string()
+}
+
+async fn install_floorp(
+    app: &AppHandle,
+    config: &InstallerConfig,
+    download_options: &DownloadOptions,
+    control: &InstallControl,
+    reports: &SignatureReports,
+    cancel: &CancelToken,
+    use_admin: bool,
+    custom_install_path: Option<String>,
+    channel: Option<ReleaseChannel>,
 ) -> Result<String, String> {
     // Check disk space requirements before proceeding
     match check_disk_space_requirements(custom_install_path.as_deref()) {
@@ -226,15 +335,6 @@ async fn download_and_run_installer(
         }
     }
 
-    let url = match get_latest_installer_url().await {
-        Some(url) => url,
-        None => return Err("rust.errors.installer_not_found".to_string()),
-    };
-
-    println!(
-        "[INFO] Downloading Floorp installer from: {}",
-        url
-    );
     println!(
         "[INFO] Installation mode: {}",
         if use_admin {
@@ -248,26 +348,114 @@ async fn download_and_run_installer(
         println!("[INFO] Custom installation path: {}", path);
     }
 
-    let filename = url.split('/').last().unwrap();
-    let temp_dir = env::temp_dir();
-    let path = temp_dir.join(filename);
+    let selector = asset_selector(config);
+    let names: Vec<String> = selector
+        .candidates()
+        .into_iter()
+        .map(|asset| asset.name)
+        .collect();
+    let exe_dir = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from));
+    let args: Vec<String> = env::args().skip(1).collect();
+    let local = offline::find(&args, exe_dir.as_deref(), &names)
+        .map_err(|e| format!("rust.errors.offline_installer|{}", e))?;
+    let mut policy = config.signing.clone();
+    if local.is_some() {
+        // A local installer is for installing without a network, where
+        // revocation servers are out of reach too.
+        policy.revocation = policy.revocation.without_network();
+    }
+
+    // Holds every file of this run and is removed when it goes out of scope,
+    // however the install ends.
+    let workspace =
+        Workspace::create().map_err(|e| format!("rust.errors.workspace_creation|{}", e))?;
+
+    let staged = match local {
+        Some(local) => stage_local_installer(&local, &workspace, cancel).await?,
+        None => {
+            let channel = channel.unwrap_or_default();
+            download_installer(
+                app,
+                config,
+                download_options,
+                &selector,
+                channel,
+                &workspace,
+                cancel,
+            )
+            .await?
+        }
+    };
+    let path = staged.path.clone();
 
-    if let Err(e) = download_file(&url, &path).await {
-        return Err(format!("rust.errors.download_failed|{}", e));
+    if let Some(expected) = &staged.expected_sha256 {
+        if staged.sha256 != *expected {
+            println!(
+                "[ERROR] Checksum mismatch: expected {}, got {}",
+                expected, staged.sha256
+            );
+            let _ = fs::remove_file(&path);
+            return Err("rust.errors.checksum_mismatch".to_string());
+        }
+        println!("[INFO] Installer checksum verified: {}", staged.sha256);
     }
 
     {
-        match check_downloaded_installer_code_sign(&path).await {
-            Ok(true) => println!("[INFO] Installer signature verification successful"),
-            Ok(false) => {
+        // WinVerifyTrust blocks, so it runs off the async runtime where the
+        // cancel token can still interrupt the wait.
+        let verifying = path.clone();
+        let verification = cancel
+            .guard(tokio::task::spawn_blocking(move || {
+                let mut report = SignatureReport::default();
+                let verified =
+                    check_downloaded_installer_code_sign(&verifying, &policy, &mut report);
+                (verified, report)
+            }))
+            .await
+            .map(|joined| {
+                joined.map(|(verified, report)| {
+                    if verified != Ok(true) {
+                        println!(
+                            "[INFO] Signature report: {}",
+                            serde_json::to_string(&report).unwrap_or_default()
+                        );
+                    }
+                    reports.record(report);
+                    verified
+                })
+            });
+        match verification {
+            Ok(Ok(Ok(true))) => println!("[INFO] Installer signature verification successful"),
+            Ok(Ok(Ok(false))) => {
                 return Err("rust.errors.signature_verification_failed".to_string())
             }
-            Err(e) => return Err(format!("rust.errors.signature_verification_error|{}", e)),
+            // Failures with their own message are passed on as they are.
+            Ok(Ok(Err(e))) if e.starts_with("rust.errors.") => return Err(e),
+            Ok(Ok(Err(e))) => return Err(format!("rust.errors.signature_verification_error|{}", e)),
+            Ok(Err(e)) => return Err(format!("rust.errors.signature_verification_error|{}", e)),
+            Err(Cancelled) => return Err(cancelled_install(&path).await),
+        }
+    }
+    if cancel.is_cancelled() {
+        return Err(cancelled_install(&path).await);
+    }
+
+    // Kept so that a retry after a failed install does not download again.
+    if let (Some(cache), Some(version)) = (InstallerCache::open(&config.cache), &staged.version) {
+        if let Err(e) = cache.store(version, &staged.sha256, &path).await {
+            println!("[WARN] Could not cache the installer: {}", e);
         }
     }
 
     println!("[INFO] Running Floorp installer...");
-    match run_installer(&path, use_admin, custom_install_path).await {
+    control.enter(if use_admin {
+        InstallPhase::RunningElevated
+    } else {
+        InstallPhase::RunningInstaller
+    });
+    match run_installer(&path, &workspace, use_admin, custom_install_path, cancel).await {
         Ok(status) => {
             if status.success {
                 return Ok("rust.success.installation_complete".to_string());
@@ -275,12 +463,194 @@ async fn download_and_run_installer(
                 return Err(format!("rust.errors.installer_exit_code|{}", status.code));
             }
         }
+        Err(e) if e == CANCELLED_ERROR => Err(cancelled_install(&path).await),
         Err(e) => {
             return Err(format!("rust.errors.installer_execution|{}", e))
         }
     }
 }
 
+/// An installer placed in the workspace, ready to be checked.
+struct StagedInstaller {
+    path: PathBuf,
+    sha256: String,
+    /// The digest it must have, if one is known.
+    expected_sha256: Option<String>,
+    /// The release version it is cached under once verified.
+    version: Option<String>,
+}
+
+/// Copies a local installer to the workspace.
+async fn stage_local_installer(
+    local: &LocalInstaller,
+    workspace: &Workspace,
+    cancel: &CancelToken,
+) -> Result<StagedInstaller, String> {
+    println!("[INFO] Using local installer: {:?}", local);
+
+    let expected = local
+        .expected_sha256()
+        .await
+        .map_err(|e| format!("rust.errors.offline_installer|{}", e))?;
+    let path = workspace.file(&local.file_name());
+    let actual = match cancel.guard(local.write_to(&path)).await {
+        Ok(written) => written.map_err(|e| format!("rust.errors.offline_installer|{}", e))?,
+        Err(Cancelled) => return Err(cancelled_install(&path).await),
+    };
+
+    Ok(StagedInstaller {
+        path,
+        sha256: actual,
+        expected_sha256: Some(expected),
+        version: None,
+    })
+}
+
+/// Resolves the installer for `channel` and downloads it to the workspace,
+/// unless a verified copy of that version is cached.
+async fn download_installer(
+    app: &AppHandle,
+    config: &InstallerConfig,
+    download_options: &DownloadOptions,
+    selector: &AssetSelector,
+    channel: ReleaseChannel,
+    workspace: &Workspace,
+    cancel: &CancelToken,
+) -> Result<StagedInstaller, String> {
+    let client = http::client().map_err(|e| format!("rust.errors.download_failed|{}", e))?;
+    let resolving = release::resolve_installer(&client, &config.release_sources, &channel, selector);
+    let installer =
+        match cancel.guard(resolving).await.map_err(|_| CANCELLED_ERROR.to_string())? {
+            Ok(installer) => installer,
+            Err(failures) => {
+                for failure in &failures {
+                    println!("[ERROR] {}", failure);
+                }
+                return Err(source_failure_error(
+                    &failures,
+                    "rust.errors.installer_not_found",
+                ));
+            }
+        };
+    if let Some(version) = &installer.version {
+        println!("[INFO] Resolved Floorp version: {}", version);
+    }
+    if let Some(size) = installer.size {
+        println!("[INFO] Installer size: {} bytes", size);
+    }
+
+    // The selected build may be for an emulated architecture, whose
+    // requirements were not checked at startup.
+    let missing = arch::missing_features(installer.asset.arch, arch::detect_feature);
+    if !missing.is_empty() {
+        return Err(format!(
+            "rust.errors.cpu_features_missing|{}",
+            feature_names(&missing).join(", ")
+        ));
+    }
+    let url = installer.url;
+
+    if let Some(size) = installer.size {
+        check_download_space(workspace.path(), size)?;
+    }
+
+    let path = workspace.file(&installer.asset.file_name());
+
+    let cache = InstallerCache::open(&config.cache);
+    if let (Some(cache), Some(version)) = (&cache, &installer.version) {
+        if let Some(sha256) = cache.fetch(version, installer.sha256.as_deref(), &path).await {
+            println!("[INFO] Using the cached installer for {}", version);
+            return Ok(StagedInstaller {
+                path,
+                sha256,
+                expected_sha256: installer.sha256.clone(),
+                version: installer.version.clone(),
+            });
+        }
+    }
+
+    println!(
+        "[INFO] Downloading Floorp installer from: {}",
+        url
+    );
+
+    let report_progress = |progress: &DownloadProgress| {
+        let _ = app.emit(PROGRESS_EVENT, progress);
+    };
+    let downloading = download::download_file(
+        &client,
+        &url,
+        &path,
+        download_options,
+        report_progress,
+    );
+    let downloaded = match cancel.guard(downloading).await {
+        Ok(Ok(downloaded)) => downloaded,
+        Ok(Err(DownloadError::Tls(problem))) => {
+            println!("[ERROR] TLS connection failed: {}", problem);
+            return Err(format!("rust.errors.tls_failed|{}", problem));
+        }
+        Ok(Err(DownloadError::DiskFull { needed })) => {
+            println!("[ERROR] The download ran out of disk space");
+            return Err(disk_full_error(&path, needed));
+        }
+        Ok(Err(DownloadError::Incomplete { got, expected })) => {
+            return Err(format!("rust.errors.download_incomplete|{}/{}", got, expected));
+        }
+        Ok(Err(e)) => return Err(format!("rust.errors.download_failed|{}", e)),
+        Err(Cancelled) => return Err(cancelled_install(&path).await),
+    };
+
+    Ok(StagedInstaller {
+        path,
+        sha256: downloaded.sha256,
+        expected_sha256: installer.sha256,
+        version: installer.version,
+    })
+}
+
+/// Maps failed release lookups to an error key, preferring a rate-limit
+/// message (with the reset time, if known), then a TLS failure, over the
+/// generic `fallback`.
+fn source_failure_error(failures: &[SourceFailure], fallback: &str) -> String {
+    let reset_at = failures.iter().find_map(|failure| match failure.error {
+        ResolveError::RateLimited { reset_at } => Some(reset_at),
+        _ => None,
+    });
+
+    match reset_at {
+        Some(Some(reset_at)) => {
+            format!("rust.errors.rate_limited|{}", release::format_utc(reset_at))
+        }
+        Some(None) => "rust.errors.rate_limited_no_reset".to_string(),
+        None => failures
+            .iter()
+            .find_map(|failure| match &failure.error {
+                ResolveError::Tls(problem) => Some(format!("rust.errors.tls_failed|{}", problem)),
+                _ => None,
+            })
+            .unwrap_or_else(|| fallback.to_string()),
+    }
+}
+
+#[tauri::command]
+async fn list_installer_versions(
+    config: State<'_, InstallerConfig>,
+) -> Result<Vec<ReleaseSummary>, String> {
+    let selector = asset_selector(&config);
+    let client = http::client().map_err(|e| {
+        println!("[ERROR] {}", e);
+        "rust.errors.release_list_failed".to_string()
+    })?;
+    match release::list_releases(&client, &config.release_sources, &selector).await {
+        Ok(releases) => Ok(releases),
+        Err(failures) => Err(source_failure_error(
+            &failures,
+            "rust.errors.release_list_failed",
+        )),
+    }
+}
+
 #[tauri::command]
 async fn launch_floorp_browser() -> Result<(), String> {
     println!("[INFO] Launching Floorp browser");
@@ -391,54 +761,6 @@ async fn exit_application() -> Result<(), String> {
     std::process::exit(0);
 }
 
-async fn get_latest_installer_url() -> Option<String> {
-    let client = Client::new();
-    let resp = client
-        .get("https://api.github.com/repos/Floorp-Projects/Floorp/releases/latest")
-        .header("User-Agent", "Floorp-Installer")
-        .send()
-        .await
-        .ok()?;
-
-    let json: serde_json::Value = resp.json().await.ok()?;
-
-    for asset in json["assets"].as_array()? {
-        let name = asset["name"].as_str()?;
-        // Look specifically for floorp-windows-x86_64.installer.exe
-        if name == "floorp-windows-x86_64.installer.exe" {
-            println!("[INFO] Found target installer: {}", name);
-            return asset["browser_download_url"]
-                .as_str()
-                .map(|s| s.to_string());
-        }
-    }
-
-    println!("[WARN] floorp-windows-x86_64.installer.exe not found in release assets");
-    None
-}
-
-async fn download_file(url: &str, path: &PathBuf) -> Result<(), String> {
-    let client = Client::new();
-    let resp = client
-        .get(url)
-        .send()
-        .await
-        .map_err(|e| format!("HTTP request error: {}", e))?
-        .bytes()
-        .await
-        .map_err(|e| format!("Response read error: {}", e))?;
-
-    let mut file = File::create(path)
-        .await
-        .map_err(|e| format!("File creation error: {}", e))?;
-
-    file.write_all(&resp)
-        .await
-        .map_err(|e| format!("File write error: {}", e))?;
-
-    Ok(())
-}
-
 fn wide_null(s: &str) -> Vec<u16> {
     OsStr::new(s)
         .encode_wide()
@@ -446,42 +768,124 @@ fn wide_null(s: &str) -> Vec<u16> {
         .collect()
 }
 
-fn verify_signature(path: &PathBuf) -> Result<bool, String> {
+fn verify_signature(
+    path: &PathBuf,
+    policy: &TrustPolicy,
+    report: &mut SignatureReport,
+) -> Result<bool, String> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+
+    // Timestamp authorities have to chain to one of these, and signatures
+    // usually leave out the root, which the pins may name.
+    let roots = signing::system_roots();
+    let signature = match signing::verify_file(path, now, &roots) {
+        Ok(signature) => signature,
+        Err(SignatureError::Io(e)) => return Err(format!("Failed to read the installer: {}", e)),
+        Err(e) => {
+            report.reject(&e);
+            return signature_error(e);
+        }
+    };
+    report.record_signature(&signature);
+    match &signature.timestamp {
+        Some(timestamp) => println!(
+            "[INFO] Timestamped {} by {}",
+            signing::format_time(timestamp.time),
+            timestamp.authority
+        ),
+        None => println!("[WARN] The signature has no timestamp"),
+    }
+
+    // Windows checks the signature again, along with the chain's root and
+    // whether any of it was revoked.
     let path_str = path.to_string_lossy().to_string();
     let file_path = wide_null(&path_str);
-
-    let signature_valid = verify_signature_validity(&file_path)?;
+    let signature_valid = verify_signature_validity(&file_path, policy, report)?;
     if !signature_valid {
         return Ok(false);
     }
 
-    match get_signer_name(&file_path)? {
-        Some(signer_name) => {
-            println!("[INFO] Signer: {}", signer_name);
+    let chain = signing::complete_chain(signature.chain, &roots);
+    let signer = &chain[0];
+    println!(
+        "[INFO] Signer: {} (issued by {}, SHA-256 {})",
+        signer.subject,
+        signer.issuer,
+        signer.thumbprint()
+    );
+    for issuer in &chain[1..] {
+        println!(
+            "[INFO] Issuer: {} (SPKI SHA-256 {})",
+            issuer.subject,
+            issuer.spki_sha256()
+        );
+    }
 
-            let is_trusted = EXPECTED_SIGNERS
-                .iter()
-                .any(|&trusted| signer_name.contains(trusted));
+    match signing::evaluate(policy, &chain, now) {
+        Ok(found) => {
+            println!(
+                "[INFO] Signer accepted by pin {} (chain depth {})",
+                found.label, found.depth
+            );
+            report.pin = Some(found.label);
+            Ok(true)
+        }
+        Err(e) => {
+            println!("[WARN] Signer is not trusted: {}", e);
+            report.fail(Rule::Pinning, e.to_string());
+            Ok(false)
+        }
+    }
+}
 
-            if is_trusted {
-                println!("[INFO] Signer is in the trusted list");
-                Ok(true)
-            } else {
-                println!(
-                    "[WARN] Signer is not in the trusted list: {}",
-                    signer_name
-                );
-                Ok(false)
-            }
+/// The error key for a signature the portable check rejected, or `false`
+/// when it has none of its own.
+fn signature_error(error: SignatureError) -> Result<bool, String> {
+    match error {
+        SignatureError::TimestampInvalid(reason) => {
+            Err(format!("rust.errors.signature_timestamp_invalid|{}", reason))
         }
-        None => {
-            println!("[WARN] Could not retrieve signer information");
+        SignatureError::TimestampOutsideValidity {
+            time,
+            not_before,
+            not_after,
+        } => Err(format!(
+            "rust.errors.signature_timestamp_outside_validity|{}|{}|{}",
+            signing::format_time(time),
+            signing::format_time(not_before),
+            signing::format_time(not_after)
+        )),
+        SignatureError::CertificateExpired {
+            not_before,
+            not_after,
+        } => Err(format!(
+            "rust.errors.signature_certificate_expired|{}|{}",
+            signing::format_time(not_before),
+            signing::format_time(not_after)
+        )),
+        e => {
+            println!("[WARN] Signature check failed: {}", e);
             Ok(false)
         }
     }
 }
 
-fn verify_signature_validity(file_path: &[u16]) -> Result<bool, String> {
+fn verify_signature_validity(
+    file_path: &[u16],
+    policy: &TrustPolicy,
+    report: &mut SignatureReport,
+) -> Result<bool, String> {
+    let (revocation_checks, provider_flags) = match policy.revocation {
+        RevocationCheck::Off => (WTD_REVOKE_NONE, WTD_REVOCATION_CHECK_NONE),
+        RevocationCheck::CachedOnly => (
+            WTD_REVOKE_WHOLECHAIN,
+            WTD_REVOCATION_CHECK_CHAIN | WTD_CACHE_ONLY_URL_RETRIEVAL,
+        ),
+        RevocationCheck::Online => (WTD_REVOKE_WHOLECHAIN, WTD_REVOCATION_CHECK_CHAIN),
+    };
+
     let file_info_box = Box::new(WINTRUST_FILE_INFO {
         cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
         pcwszFilePath: PCWSTR(file_path.as_ptr()),
@@ -496,7 +900,7 @@ fn verify_signature_validity(file_path: &[u16]) -> Result<bool, String> {
         pPolicyCallbackData: null_mut(),
         pSIPClientData: null_mut(),
         dwUIChoice: WTD_UI_NONE,
-        fdwRevocationChecks: WTD_REVOKE_NONE,
+        fdwRevocationChecks: revocation_checks,
         dwUnionChoice: WTD_CHOICE_FILE,
         Anonymous: WINTRUST_DATA_0 {
             pFile: file_info_ptr,
@@ -504,7 +908,7 @@ fn verify_signature_validity(file_path: &[u16]) -> Result<bool, String> {
         dwStateAction: WTD_STATEACTION_VERIFY,
         hWVTStateData: HANDLE::default(),
         pwszURLReference: PWSTR::null(),
-        dwProvFlags: WINTRUST_DATA_PROVIDER_FLAGS(0),
+        dwProvFlags: provider_flags,
         dwUIContext: WINTRUST_DATA_UICONTEXT(0),
         pSignatureSettings: null_mut(),
     };
@@ -522,82 +926,48 @@ fn verify_signature_validity(file_path: &[u16]) -> Result<bool, String> {
         ret
     };
 
-    println!("[INFO] Windows API signature verification result: {}", result);
+    let status = signing::win_verify_trust_status(result);
+    println!("[INFO] Windows API signature verification result: {}", status);
+    report.windows_status = Some(status.clone());
 
-    Ok(result == 0)
-}
-
-fn get_signer_name(file_path: &[u16]) -> Result<Option<String>, String> {
-    unsafe {
-        let mut encoding_type = CERT_QUERY_ENCODING_TYPE::default();
-        let mut content_type = CERT_QUERY_CONTENT_TYPE::default();
-        let mut format_type = CERT_QUERY_FORMAT_TYPE::default();
-        let mut cert_store: HCERTSTORE = HCERTSTORE::default();
-        let mut msg = null_mut::<c_void>();
-        let mut context = null_mut::<c_void>();
-
-        let success = CryptQueryObject(
-            CERT_QUERY_OBJECT_FILE,
-            transmute(PCWSTR(file_path.as_ptr())),
-            CERT_QUERY_CONTENT_FLAG_PKCS7_SIGNED_EMBED,
-            CERT_QUERY_FORMAT_FLAG_BINARY,
-            0,
-            Some(&mut encoding_type),
-            Some(&mut content_type),
-            Some(&mut format_type),
-            Some(&mut cert_store),
-            Some(&mut msg),
-            Some(&mut context),
-        );
-
-        if !success.is_ok() {
-            let error = GetLastError();
-            return Err(format!(
-                "CryptQueryObject failed: error code {}",
-                error.0
-            ));
-        }
-
-        let cert_context = windows::Win32::Security::Cryptography::CertFindCertificateInStore(
-            cert_store,
-            encoding_type,
-            0,
-            windows::Win32::Security::Cryptography::CERT_FIND_ANY,
-            Some(null_mut::<c_void>()),
-            None,
-        );
-
-        if cert_context.is_null() {
-            return Ok(None);
-        }
-
-        let mut name_buf = vec![0u16; 256];
-
-        let name_len = CertGetNameStringW(
-            cert_context,
-            CERT_NAME_SIMPLE_DISPLAY_TYPE,
-            0,
-            Some(null_mut::<c_void>()),
-            Some(&mut name_buf),
-        );
-
-        if name_len == 0 {
-            let _ = windows::Win32::Security::Cryptography::CertFreeCertificateContext(Some(
-                cert_context,
-            ));
-            return Ok(None);
+    let code = HRESULT(result);
+    if code == CERT_E_REVOKED || code == CRYPT_E_REVOKED {
+        report.fail(Rule::Revocation, "a certificate of the chain is revoked");
+        return Err("rust.errors.signature_revoked".to_string());
+    }
+    if code == CERT_E_REVOCATION_FAILURE
+        || code == CRYPT_E_NO_REVOCATION_CHECK
+        || code == CRYPT_E_REVOCATION_OFFLINE
+    {
+        if policy.requires_revocation() {
+            report.fail(Rule::Revocation, "revocation could not be checked");
+            return Err("rust.errors.signature_revocation_unknown".to_string());
         }
-
-        let name = String::from_utf16_lossy(&name_buf[0..(name_len as usize - 1)]);
-
-        let _ =
-            windows::Win32::Security::Cryptography::CertFreeCertificateContext(Some(cert_context));
-
-        Ok(Some(name))
+        // A local installer, offline or behind a captive portal: the rest of
+        // the chain checked out, and the pins still have to match.
+        println!("[WARN] Revocation could not be checked, continuing without");
+        report.revocation_unknown = Some(status);
+        return Ok(true);
+    }
+    if code == TRUST_E_TIME_STAMP {
+        report.fail(Rule::Timestamp, "Windows rejected the timestamp");
+        return Err(format!(
+            "rust.errors.signature_timestamp_invalid|WinVerifyTrust {:#010x}",
+            result as u32
+        ));
+    }
+    if result != 0 {
+        report.fail(Rule::Windows, status);
+        return Ok(false);
     }
+    Ok(true)
 }
 
-async fn check_downloaded_installer_code_sign(path: &PathBuf) -> Result<bool, String> {
+fn check_downloaded_installer_code_sign(
+    path: &PathBuf,
+    policy: &TrustPolicy,
+    report: &mut SignatureReport,
+) -> Result<bool, String> {
     if !path.exists() {
         return Err(format!("rust.errors.file_not_found|{}", path.display()));
     }
@@ -605,7 +975,7 @@ async fn check_downloaded_installer_code_sign(path: &PathBuf) -> Result<bool, St
         return Err(format!("rust.errors.not_a_file|{}", path.display()));
     }
 
-    return verify_signature(path);
+    return verify_signature(path, policy, report);
 }
 
 struct InstallerStatus {
@@ -615,13 +985,14 @@ struct InstallerStatus {
 
 async fn run_installer(
     path: &PathBuf,
+    workspace: &Workspace,
     use_admin: bool,
     custom_install_path: Option<String>,
+    cancel: &CancelToken,
 ) -> Result<InstallerStatus, String> {
     println!("[INFO] Running installer: {}", path.display());
 
-    let temp_dir = env::temp_dir();
-    let config_ini = temp_dir.join("floorp_install_config.ini");
+    let config_ini = workspace.file("floorp_install_config.ini");
 
     let install_dir = if let Some(custom_path) = &custom_install_path {
         custom_path.clone()
@@ -676,7 +1047,7 @@ async fn run_installer(
     if use_admin {
         println!("[INFO] Running installer with administrator privileges");
 
-        let script_path = temp_dir.join("run_floorp_installer.ps1");
+        let script_path = workspace.file("run_floorp_installer.ps1");
         let script_content = format!(
             "try {{\n\
              $ErrorActionPreference = 'Stop';\n\
@@ -700,6 +1071,12 @@ async fn run_installer(
             Err(e) => return Err(format!("rust.errors.powershell_script_creation|{}", e)),
         }
 
+        // The elevated installer is out of reach of this process, so it is
+        // waited for to the end: cancel is refused from here on, and the
+        // workspace stays until the installer no longer uses it.
+        if cancel.is_cancelled() {
+            return Err(CANCELLED_ERROR.to_string());
+        }
         let child = Command::new("powershell.exe")
             .arg("-ExecutionPolicy")
             .arg("Bypass")
@@ -709,8 +1086,6 @@ async fn run_installer(
             .await
             .map_err(|e| format!("rust.errors.powershell_process|{}", e))?;
 
-        let _ = std::fs::remove_file(&script_path);
-
         let output = String::from_utf8_lossy(&child.stdout);
         let error_output = String::from_utf8_lossy(&child.stderr);
 
@@ -756,11 +1131,14 @@ async fn run_installer(
             return Err(format!("rust.errors.admin_install_failed|{}", exit_code));
         }
     } else {
-        status = run_installer_user_mode(path, &config_ini, custom_install_path.as_deref()).await?;
-    }
-
-    if config_ini.exists() {
-        let _ = std::fs::remove_file(&config_ini);
+        status = run_installer_user_mode(
+            path,
+            workspace,
+            &config_ini,
+            custom_install_path.as_deref(),
+            cancel,
+        )
+        .await?;
     }
 
     sleep(Duration::from_secs(2)).await;
@@ -770,14 +1148,15 @@ async fn run_installer(
 
 async fn run_installer_user_mode(
     path: &PathBuf,
+    workspace: &Workspace,
     config_ini: &PathBuf,
     custom_install_path: Option<&str>,
+    cancel: &CancelToken,
 ) -> Result<InstallerStatus, String> {
     println!("[INFO] Running installer in user mode");
 
     let user_config_ini = if !config_ini.exists() {
-        let temp_dir = env::temp_dir();
-        let user_config = temp_dir.join("floorp_user_install_config.ini");
+        let user_config = workspace.file("floorp_user_install_config.ini");
 
         let user_install_dir = if let Some(custom_path) = custom_install_path {
             custom_path.to_string()
@@ -830,7 +1209,16 @@ async fn run_installer_user_mode(
         format!("rust.errors.user_installer_launch|{}", e)
     })?;
 
-    let result = match child.wait().await {
+    let waited = match cancel.guard(child.wait()).await {
+        Ok(waited) => waited,
+        Err(Cancelled) => {
+            println!("[INFO] Stopping the installer");
+            let _ = child.kill().await;
+            return Err(CANCELLED_ERROR.to_string());
+        }
+    };
+
+    match waited {
         Ok(status) => {
             let exit_code = status.code().unwrap_or(-1);
             println!(
@@ -844,13 +1232,7 @@ async fn run_installer_user_mode(
             })
         }
         Err(e) => Err(format!("rust.errors.user_installer_execution|{}", e)),
-    };
-
-    if let Some(user_config) = user_config_ini {
-        let _ = std::fs::remove_file(user_config);
     }
-
-    result
 }
 
 async fn check_webview2_runtime() -> Result<bool, String> {
@@ -922,11 +1304,14 @@ async fn check_webview2_runtime() -> Result<bool, String> {
     Ok(false)
 }
 
-async fn download_and_install_webview2_runtime() -> Result<bool, String> {
+async fn download_and_install_webview2_runtime(policy: &TrustPolicy) -> Result<bool, String> {
     println!("[INFO] Downloading WebView2 Runtime bootstrapper");
 
-    let temp_dir = env::temp_dir();
-    let notify_script_path = temp_dir.join("webview2_install_notify.ps1");
+    // The bootstrapper and the notification script are run from a private
+    // workspace, where nothing can swap them after they are written.
+    let workspace = Workspace::create()
+        .map_err(|e| format!("Failed to create WebView2 Runtime workspace: {}", e))?;
+    let notify_script_path = workspace.file("webview2_install_notify.ps1");
     let notify_script = r#"
     Add-Type -AssemblyName System.Windows.Forms
     $form = New-Object System.Windows.Forms.Form
@@ -961,30 +1346,41 @@ async fn download_and_install_webview2_runtime() -> Result<bool, String> {
         .map_err(|e| format!("Failed to show notification: {}", e))?;
 
     let url = "https://go.microsoft.com/fwlink/p/?LinkId=2124703";
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-
-    let installer_path = temp_dir.join(format!("MicrosoftEdgeWebview2Setup_{}.exe", timestamp));
-
-    for entry in std::fs::read_dir(&temp_dir).unwrap_or_else(|_| std::fs::read_dir(".").unwrap()) {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if let Some(file_name) = path.file_name() {
-                if let Some(file_name_str) = file_name.to_str() {
-                    if file_name_str.starts_with("MicrosoftEdgeWebview2Setup_") && file_name_str.ends_with(".exe") {
-                        let _ = std::fs::remove_file(&path);
-                    }
-                }
-            }
-        }
-    }
+    let installer_path = workspace.file("MicrosoftEdgeWebview2Setup.exe");
 
-    if let Err(e) = download_file(url, &installer_path).await {
+    let client = http::client()?;
+    let options = DownloadOptions::default();
+    if let Err(e) = download::download_file(&client, url, &installer_path, &options, |_| {}).await {
         return Err(format!("Failed to download WebView2 Runtime installer: {}", e));
     }
 
+    // The bootstrapper is run as soon as it is here, so it goes through the
+    // same checks as the Floorp installer, against Microsoft's certificates.
+    let verifying = installer_path.clone();
+    let webview2_policy = policy.clone();
+    let (verified, report) = tokio::task::spawn_blocking(move || {
+        let mut report = SignatureReport::default();
+        let verified =
+            check_downloaded_installer_code_sign(&verifying, &webview2_policy, &mut report);
+        (verified, report)
+    })
+    .await
+    .map_err(|e| format!("Failed to verify WebView2 Runtime installer: {}", e))?;
+    if verified != Ok(true) {
+        println!(
+            "[INFO] Signature report: {}",
+            serde_json::to_string(&report).unwrap_or_default()
+        );
+        close_webview2_notification();
+        let reason = match (report.failure, verified) {
+            (Some(failure), _) => failure.reason,
+            (None, Err(e)) => e,
+            (None, Ok(_)) => "the signature was not accepted".to_string(),
+        };
+        return Err(format!("{}: {}", WEBVIEW2_SIGNATURE_ERROR, reason));
+    }
+    println!("[INFO] WebView2 Runtime installer signature verified");
+
     println!("[INFO] Running WebView2 Runtime installer from: {}", installer_path.display());
 
     tokio::time::sleep(Duration::from_secs(1)).await;
@@ -1003,36 +1399,13 @@ async fn download_and_install_webview2_runtime() -> Result<bool, String> {
                     Ok(status) => {
                         if status.success() {
                             println!("[INFO] WebView2 Runtime installation completed successfully");
-                            let _ = tokio::fs::remove_file(&installer_path).await;
-
-                            let close_dialog_script = r#"
-                            Get-Process | Where-Object { $_.MainWindowTitle -eq "Floorp Installer | WebView2 Runtime" } | ForEach-Object { $_.CloseMainWindow() }
-                            "#;
-                            let close_script_path = temp_dir.join("close_webview2_notify.ps1");
-                            let _ = std::fs::write(&close_script_path, close_dialog_script);
-                            let _ = Command::new("powershell.exe")
-                                .arg("-ExecutionPolicy")
-                                .arg("Bypass")
-                                .arg("-Command")
-                                .arg(close_dialog_script)
-                                .spawn()
-                                .map_err(|e| println!("[WARN] Failed to close notification dialog: {}", e));
+                            close_webview2_notification();
 
                             return Ok(true);
                         } else {
                             let code = status.code().unwrap_or(-1);
                             println!("[ERROR] WebView2 Runtime installation failed with exit code: {}", code);
-                            let _ = tokio::fs::remove_file(&installer_path).await;
-                            let close_dialog_script = r#"
-                            Get-Process | Where-Object { $_.MainWindowTitle -eq "Floorp Installer | WebView2 Runtime" } | ForEach-Object { $_.CloseMainWindow() }
-                            "#;
-                            let _ = Command::new("powershell.exe")
-                                .arg("-ExecutionPolicy")
-                                .arg("Bypass")
-                                .arg("-Command")
-                                .arg(close_dialog_script)
-                                .spawn()
-                                .map_err(|e| println!("[WARN] Failed to close notification dialog: {}", e));
+                            close_webview2_notification();
 
                             return Ok(false);
                         }
@@ -1055,12 +1428,28 @@ async fn download_and_install_webview2_runtime() -> Result<bool, String> {
     Err(last_error)
 }
 
+/// Closes the window telling the user that WebView2 Runtime is installing.
+fn close_webview2_notification() {
+    let close_dialog_script = r#"
+    Get-Process | Where-Object { $_.MainWindowTitle -eq "Floorp Installer | WebView2 Runtime" } | ForEach-Object { $_.CloseMainWindow() }
+    "#;
+    let _ = Command::new("powershell.exe")
+        .arg("-ExecutionPolicy")
+        .arg("Bypass")
+        .arg("-Command")
+        .arg(close_dialog_script)
+        .spawn()
+        .map_err(|e| println!("[WARN] Failed to close notification dialog: {}", e));
+}
+
 #[tauri::command]
-async fn check_and_install_webview2_runtime() -> Result<String, String> {
+async fn check_and_install_webview2_runtime(
+    config: State<'_, InstallerConfig>,
+) -> Result<String, String> {
     match check_webview2_runtime().await {
         Ok(true) => Ok("WebView2 Runtime is already installed".to_string()),
         Ok(false) => {
-            match download_and_install_webview2_runtime().await {
+            match download_and_install_webview2_runtime(&config.webview2_signing).await {
                 Ok(true) => Ok("WebView2 Runtime was successfully installed".to_string()),
                 Ok(false) => Err("WebView2 Runtime installation failed".to_string()),
                 Err(e) => Err(format!("WebView2 Runtime installation error: {}", e)),
@@ -1073,25 +1462,54 @@ async fn check_and_install_webview2_runtime() -> Result<String, String> {
 fn main() {
     check_cpu_requirements(); // Call the new CPU check function
 
+    let mut config = config::load();
+    let http_settings = HttpSettings {
+        proxy: config.proxy.clone(),
+        tls: config.tls.clone(),
+        timeouts: config.timeouts.clone(),
+        retry: config.retry.clone(),
+    };
+    let args: Vec<String> = env::args().skip(1).collect();
+    let http_settings = http_settings
+        .clone()
+        .with_overrides(&args, |name| env::var(name).ok())
+        .unwrap_or_else(|e| {
+            println!("[ERROR] {}", e);
+            http_settings
+        });
+    if let Err(e) = http::init(&http_settings) {
+        println!("[ERROR] {}", e);
+    }
+    match config.download.clone().with_overrides(&args) {
+        Ok(download) => config.download = download,
+        Err(e) => println!("[ERROR] {}", e),
+    }
+
     tauri::Builder::default()
+        .manage(config)
+        .manage(InstallControl::default())
+        .manage(SignatureReports::default())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .invoke_handler(tauri::generate_handler![
             download_and_run_installer,
+            cancel_install,
+            get_signature_report,
             launch_floorp_browser,
             exit_application,
             check_and_install_webview2_runtime,
             check_cpu_support,
-            check_disk_space
+            check_disk_space,
+            list_installer_versions
         ])
         .setup(|app| {
             let app_handle = app.handle().clone();
 
             tauri::async_runtime::spawn(async move {
                 // Check CPU support first
-                if !check_sse4_1_support() {
-                    println!("[ERROR] CPU does not support SSE4.1 instructions");
-                    println!("[ERROR] Floorp requires a processor with SSE4.1 support");
+                let missing = missing_cpu_features();
+                if !missing.is_empty() {
+                    println!("[ERROR] CPU does not support {}", missing.join(", "));
                     println!("[ERROR] This device is not supported");
                     
                     // Close all windows and exit
@@ -1108,7 +1526,8 @@ fn main() {
                         println!("[INFO] WebView2 Runtime is already installed");
                     },
                     Ok(false) => {
-                        let result = download_and_install_webview2_runtime().await;
+                        let policy = app_handle.state::<InstallerConfig>().webview2_signing.clone();
+                        let result = download_and_install_webview2_runtime(&policy).await;
                         match result {
                             Ok(true) => {
                                 println!("[INFO] WebView2 Runtime was successfully installed");
@@ -1139,6 +1558,13 @@ fn main() {
                             Ok(false) => {
                                 println!("[ERROR] WebView2 Runtime installation failed");
                             },
+                            Err(e) if e.starts_with(WEBVIEW2_SIGNATURE_ERROR) => {
+                                // Without WebView2 the window cannot show
+                                // anything, so the user is told here.
+                                println!("[ERROR] {}", e);
+                                show_error_dialog("Floorp Installer - WebView2 Runtime", &e);
+                                std::process::exit(1);
+                            }
                             Err(e) => {
                                 println!("[ERROR] WebView2 Runtime installation error: {}", e);
                             }
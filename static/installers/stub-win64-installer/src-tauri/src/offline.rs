@@ -0,0 +1,274 @@
+//! Installing without network access.
+//!
+//! The installer can be given on the command line (`--installer <path>`,
+//! optionally with `--installer-sha256 <hex>`), placed next to the stub
+//! under its release asset name, or embedded at build time with the
+//! `offline` feature. A file's digest comes from `--installer-sha256` or a
+//! `<file>.sha256` sidecar; the embedded installer's is fixed at build time.
+//!
+//! A file next to the stub is only used together with its sidecar. The
+//! Downloads folder often holds an old installer from an earlier download,
+//! which is no reason to skip downloading the current one.
+
+use crate::checksum;
+use crate::cli;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub const INSTALLER_ARG: &str = "--installer";
+pub const INSTALLER_SHA256_ARG: &str = "--installer-sha256";
+
+/// The installer embedded with the `offline` feature, see `build.rs`.
+#[cfg(feature = "offline")]
+mod embedded {
+    pub const NAME: &str = env!("FLOORP_OFFLINE_INSTALLER_NAME");
+    pub const SHA256: &str = env!("FLOORP_OFFLINE_INSTALLER_SHA256");
+    pub static BYTES: &[u8] = include_bytes!(env!("FLOORP_OFFLINE_INSTALLER_PATH"));
+}
+
+/// An installer available without downloading.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LocalInstaller {
+    /// A file given on the command line or found next to the stub.
+    File {
+        path: PathBuf,
+        sha256: Option<String>,
+    },
+    /// The installer built into an offline stub.
+    #[cfg(feature = "offline")]
+    Embedded,
+}
+
+impl LocalInstaller {
+    /// File name to stage the installer under.
+    pub fn file_name(&self) -> String {
+        match self {
+            LocalInstaller::File { path, .. } => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            #[cfg(feature = "offline")]
+            LocalInstaller::Embedded => embedded::NAME.to_string(),
+        }
+    }
+
+    /// The digest the installer must have.
+    pub async fn expected_sha256(&self) -> Result<String, String> {
+        match self {
+            LocalInstaller::File {
+                sha256: Some(sha256),
+                ..
+            } => Ok(sha256.clone()),
+            LocalInstaller::File { path, sha256: None } => {
+                let sidecar = sidecar_path(path);
+                let contents = fs::read_to_string(&sidecar).await.map_err(|e| {
+                    format!(
+                        "no SHA-256 digest for {} (pass {} or add {}): {}",
+                        path.display(),
+                        INSTALLER_SHA256_ARG,
+                        sidecar.display(),
+                        e
+                    )
+                })?;
+                checksum::find_in_checksum_file(&contents, &self.file_name()).ok_or_else(|| {
+                    format!(
+                        "{} does not list a digest for {}",
+                        sidecar.display(),
+                        self.file_name()
+                    )
+                })
+            }
+            #[cfg(feature = "offline")]
+            LocalInstaller::Embedded => Ok(embedded::SHA256.to_string()),
+        }
+    }
+
+    /// Copies the installer to `dest` and returns the SHA-256 of what was
+    /// written, so the copy that runs is the copy that was checked.
+    pub async fn write_to(&self, dest: &Path) -> Result<String, String> {
+        let mut out = File::create(dest)
+            .await
+            .map_err(|e| format!("File creation error: {}", e))?;
+        let mut hasher = Sha256::new();
+
+        match self {
+            LocalInstaller::File { path, .. } => {
+                let mut file = File::open(path)
+                    .await
+                    .map_err(|e| format!("File open error: {}", e))?;
+                let mut buf = vec![0u8; 64 * 1024];
+                loop {
+                    let n = file
+                        .read(&mut buf)
+                        .await
+                        .map_err(|e| format!("File read error: {}", e))?;
+                    if n == 0 {
+                        break;
+                    }
+                    hasher.update(&buf[..n]);
+                    out.write_all(&buf[..n])
+                        .await
+                        .map_err(|e| format!("File write error: {}", e))?;
+                }
+            }
+            #[cfg(feature = "offline")]
+            LocalInstaller::Embedded => {
+                hasher.update(embedded::BYTES);
+                out.write_all(embedded::BYTES)
+                    .await
+                    .map_err(|e| format!("File write error: {}", e))?;
+            }
+        }
+
+        out.flush()
+            .await
+            .map_err(|e| format!("File write error: {}", e))?;
+        Ok(hex::encode(hasher.finalize()))
+    }
+}
+
+/// `<path>.sha256`, where the digest of a local file is looked up.
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".sha256");
+    PathBuf::from(sidecar)
+}
+
+/// Parses `--installer` and `--installer-sha256`.
+pub fn from_args(args: &[String]) -> Result<Option<LocalInstaller>, String> {
+    let path = cli::arg_value(args, INSTALLER_ARG)?.map(PathBuf::from);
+    // The file is staged under its name, which must not be empty.
+    if let Some(path) = path.as_ref().filter(|path| path.file_name().is_none()) {
+        return Err(format!(
+            "{} must name a file: {}",
+            INSTALLER_ARG,
+            path.display()
+        ));
+    }
+    let sha256 = cli::arg_value(args, INSTALLER_SHA256_ARG)?
+        .map(|value| {
+            checksum::parse_sha256(&value)
+                .ok_or_else(|| format!("invalid {} value: {}", INSTALLER_SHA256_ARG, value))
+        })
+        .transpose()?;
+
+    match (path, sha256) {
+        (Some(path), sha256) => Ok(Some(LocalInstaller::File { path, sha256 })),
+        (None, Some(_)) => Err(format!(
+            "{} requires {}",
+            INSTALLER_SHA256_ARG, INSTALLER_ARG
+        )),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Finds the first of `names` present in `dir` with a `.sha256` sidecar.
+pub fn find_next_to(dir: &Path, names: &[String]) -> Option<LocalInstaller> {
+    names
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .find(|path| {
+            let found = sidecar_path(path).is_file();
+            if !found {
+                println!(
+                    "[INFO] Ignoring {} without a .sha256 file next to it",
+                    path.display()
+                );
+            }
+            found
+        })
+        .map(|path| LocalInstaller::File { path, sha256: None })
+}
+
+/// Picks the installer to use without downloading, if any: one given on
+/// the command line, then the embedded one, then one next to the stub.
+pub fn find(
+    args: &[String],
+    exe_dir: Option<&Path>,
+    names: &[String],
+) -> Result<Option<LocalInstaller>, String> {
+    if let Some(installer) = from_args(args)? {
+        return Ok(Some(installer));
+    }
+
+    #[cfg(feature = "offline")]
+    {
+        let _ = (exe_dir, names);
+        Ok(Some(LocalInstaller::Embedded))
+    }
+
+    #[cfg(not(feature = "offline"))]
+    {
+        Ok(exe_dir.and_then(|dir| find_next_to(dir, names)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIGEST: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_installer_arguments() {
+        assert_eq!(from_args(&args(&["--minimized"])), Ok(None));
+        assert_eq!(
+            from_args(&args(&[
+                "--installer",
+                "D:\\floorp.exe",
+                &format!("--installer-sha256=sha256:{}", DIGEST.to_uppercase()),
+            ])),
+            Ok(Some(LocalInstaller::File {
+                path: PathBuf::from("D:\\floorp.exe"),
+                sha256: Some(DIGEST.to_string()),
+            }))
+        );
+        assert!(from_args(&args(&["--installer"])).is_err());
+        assert!(from_args(&args(&["--installer", "/"])).is_err());
+        assert!(from_args(&args(&["--installer=installers/.."])).is_err());
+        assert!(from_args(&args(&["--installer-sha256", DIGEST])).is_err());
+        assert!(from_args(&args(&["--installer=a.exe", "--installer-sha256=xyz"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn copies_installer_found_next_to_stub_and_reads_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = "floorp-windows-x86_64.installer.exe";
+        std::fs::write(dir.path().join(name), "test").unwrap();
+        std::fs::write(
+            dir.path().join(format!("{}.sha256", name)),
+            format!("{}  {}\n", DIGEST, name),
+        )
+        .unwrap();
+
+        std::fs::write(dir.path().join("stale.exe"), "old").unwrap();
+        let names = ["stale.exe".to_string(), name.to_string()];
+        let installer = find_next_to(dir.path(), &names).unwrap();
+        let staged = dir.path().join("staged.exe");
+        let actual = installer.write_to(&staged).await.unwrap();
+
+        assert_eq!(installer.expected_sha256().await.unwrap(), DIGEST);
+        assert_eq!(actual, DIGEST);
+        assert_eq!(std::fs::read(&staged).unwrap(), b"test");
+        assert_eq!(find_next_to(dir.path(), &["other.exe".to_string()]), None);
+        assert_eq!(find_next_to(dir.path(), &names[..1]), None);
+    }
+
+    #[tokio::test]
+    async fn requires_a_digest_for_local_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("floorp.exe");
+        std::fs::write(&path, "test").unwrap();
+
+        let installer = LocalInstaller::File { path, sha256: None };
+
+        assert!(installer.expected_sha256().await.is_err());
+    }
+}
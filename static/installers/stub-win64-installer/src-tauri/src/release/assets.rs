@@ -0,0 +1,209 @@
+//! Chooses the installer asset matching the machine's architecture.
+//!
+//! Each architecture has file name patterns in which `*` matches any run of
+//! characters. Patterns are tried in table order, for the native
+//! architecture first and then for the ones it can emulate.
+
+use super::INSTALLER_ASSET_NAME;
+use crate::arch::Arch;
+use crate::glob;
+use serde::Deserialize;
+
+/// A file name pattern identifying the installer for `arch`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct AssetPattern {
+    pub arch: Arch,
+    pub pattern: String,
+}
+
+/// The asset names Floorp publishes for Windows.
+pub fn default_asset_patterns() -> Vec<AssetPattern> {
+    let pattern = |arch, pattern: &str| AssetPattern {
+        arch,
+        pattern: pattern.to_string(),
+    };
+    vec![
+        pattern(Arch::Aarch64, "floorp-windows-aarch64.installer.exe"),
+        pattern(Arch::Aarch64, "floorp-windows-arm64.installer.exe"),
+        pattern(Arch::X86_64, INSTALLER_ASSET_NAME),
+    ]
+}
+
+/// The installer asset picked for this machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectedAsset {
+    pub name: String,
+    pub arch: Arch,
+}
+
+impl SelectedAsset {
+    /// The name to save the asset under: characters Windows does not allow
+    /// in file names are replaced, and an empty name falls back to the
+    /// x86_64 installer's.
+    pub fn file_name(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| match c {
+                '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect();
+        let name = name.trim_end_matches(['.', ' ']);
+        if name.is_empty() {
+            INSTALLER_ASSET_NAME.to_string()
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AssetSelector {
+    native: Arch,
+    patterns: Vec<AssetPattern>,
+}
+
+impl Default for AssetSelector {
+    fn default() -> Self {
+        AssetSelector::new(Arch::X86_64, default_asset_patterns())
+    }
+}
+
+impl AssetSelector {
+    pub fn new(native: Arch, patterns: Vec<AssetPattern>) -> Self {
+        AssetSelector { native, patterns }
+    }
+
+    /// Architectures whose builds run on this machine, best first.
+    pub fn installable(&self) -> &'static [Arch] {
+        self.native.installable()
+    }
+
+    /// Picks the best installer among the published asset `names`.
+    pub fn select<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Option<SelectedAsset> {
+        let names: Vec<&str> = names.into_iter().collect();
+
+        self.ordered_patterns().find_map(|pattern| {
+            names
+                .iter()
+                .find(|name| glob::matches(&pattern.pattern, name))
+                .map(|name| SelectedAsset {
+                    name: name.to_string(),
+                    arch: pattern.arch,
+                })
+        })
+    }
+
+    /// Literal asset names to probe, best first, for sources that can only
+    /// be asked for a file by name.
+    pub fn candidates(&self) -> Vec<SelectedAsset> {
+        self.ordered_patterns()
+            .filter(|pattern| !pattern.pattern.contains('*'))
+            .map(|pattern| SelectedAsset {
+                name: pattern.pattern.clone(),
+                arch: pattern.arch,
+            })
+            .collect()
+    }
+
+    fn ordered_patterns(&self) -> impl Iterator<Item = &AssetPattern> {
+        self.installable().iter().flat_map(move |arch| {
+            self.patterns
+                .iter()
+                .filter(move |pattern| pattern.arch == *arch)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLISHED: [&str; 3] = [
+        "floorp-linux-aarch64.tar.xz",
+        "floorp-windows-x86_64.installer.exe",
+        "floorp-windows-aarch64.installer.exe",
+    ];
+
+    fn selected(name: &str, arch: Arch) -> Option<SelectedAsset> {
+        Some(SelectedAsset {
+            name: name.to_string(),
+            arch,
+        })
+    }
+
+    #[test]
+    fn prefers_native_build_and_falls_back_to_emulation() {
+        let x64 = AssetSelector::new(Arch::X86_64, default_asset_patterns());
+        let arm64 = AssetSelector::new(Arch::Aarch64, default_asset_patterns());
+
+        assert_eq!(
+            x64.select(PUBLISHED),
+            selected("floorp-windows-x86_64.installer.exe", Arch::X86_64)
+        );
+        assert_eq!(
+            arm64.select(PUBLISHED),
+            selected("floorp-windows-aarch64.installer.exe", Arch::Aarch64)
+        );
+        assert_eq!(
+            arm64.select(PUBLISHED[..2].iter().copied()),
+            selected("floorp-windows-x86_64.installer.exe", Arch::X86_64)
+        );
+        assert_eq!(x64.select(["floorp-windows-aarch64.installer.exe"]), None);
+    }
+
+    #[test]
+    fn matches_wildcard_patterns() {
+        let selector = AssetSelector::new(
+            Arch::Aarch64,
+            vec![AssetPattern {
+                arch: Arch::Aarch64,
+                pattern: "floorp-*-windows-arm64*.exe".to_string(),
+            }],
+        );
+
+        assert_eq!(
+            selector.select(["Floorp-12.0-Windows-ARM64-setup.exe"]),
+            selected("Floorp-12.0-Windows-ARM64-setup.exe", Arch::Aarch64)
+        );
+        assert_eq!(selector.select(["floorp-12.0-windows-x64.exe"]), None);
+        assert!(selector.candidates().is_empty());
+    }
+
+    #[test]
+    fn makes_a_safe_file_name() {
+        let file_name = |name: &str| {
+            SelectedAsset {
+                name: name.to_string(),
+                arch: Arch::X86_64,
+            }
+            .file_name()
+        };
+
+        assert_eq!(file_name(INSTALLER_ASSET_NAME), INSTALLER_ASSET_NAME);
+        assert_eq!(file_name("setup.exe?sig=a&exp=1"), "setup.exe_sig=a&exp=1");
+        assert_eq!(file_name("..\\setup.exe"), ".._setup.exe");
+        assert_eq!(file_name(""), INSTALLER_ASSET_NAME);
+        assert_eq!(file_name(". "), INSTALLER_ASSET_NAME);
+    }
+
+    #[test]
+    fn lists_literal_candidates_in_preference_order() {
+        let names: Vec<_> = AssetSelector::new(Arch::Aarch64, default_asset_patterns())
+            .candidates()
+            .into_iter()
+            .map(|asset| asset.name)
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "floorp-windows-aarch64.installer.exe",
+                "floorp-windows-arm64.installer.exe",
+                "floorp-windows-x86_64.installer.exe",
+            ]
+        );
+    }
+}
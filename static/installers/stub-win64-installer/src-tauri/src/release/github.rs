@@ -0,0 +1,907 @@
+//! Release lookup through the GitHub REST API.
+
+use super::{
+    fetch_text, request_error, AssetSelector, ReleaseChannel, ReleaseSummary, ResolveError,
+    ResolvedRelease, SelectedAsset, USER_AGENT,
+};
+use crate::checksum;
+use crate::http;
+use reqwest::header::{HeaderMap, AUTHORIZATION, LOCATION, RETRY_AFTER};
+use reqwest::{Client, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// GitHub REST API root of the Floorp repository.
+pub const GITHUB_REPO_API_URL: &str = "https://api.github.com/repos/Floorp-Projects/Floorp";
+
+/// How many entries of the `/releases` list are considered.
+const RELEASE_LIST_LIMIT: u32 = 30;
+
+/// Combined checksum files that may list the installer.
+const CHECKSUM_ASSET_NAMES: [&str; 2] = ["SHA256SUMS", "SHA256SUMS.txt"];
+
+/// Environment variables checked, in order, for an API token when the
+/// config does not set one.
+const TOKEN_ENV_VARS: [&str; 2] = ["FLOORP_INSTALLER_GITHUB_TOKEN", "GITHUB_TOKEN"];
+
+/// The fields of a GitHub release object the installer relies on.
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    name: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
+    published_at: Option<String>,
+    /// Release notes, in Markdown.
+    body: Option<String>,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+    size: Option<u64>,
+    /// `sha256:<hex>`, published by GitHub for newer uploads.
+    digest: Option<String>,
+}
+
+impl Release {
+    /// The installer asset `selector` prefers for this machine.
+    fn installer(&self, selector: &AssetSelector) -> Option<(&Asset, SelectedAsset)> {
+        let selected = selector.select(self.assets.iter().map(|asset| asset.name.as_str()))?;
+        let asset = self
+            .assets
+            .iter()
+            .find(|asset| asset.name == selected.name)?;
+        Some((asset, selected))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Recognises GitHub's primary (`X-RateLimit-Remaining: 0`) and secondary
+/// (`Retry-After`) rate-limit responses.
+fn rate_limit_error(status: StatusCode, headers: &HeaderMap) -> Option<ResolveError> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let exhausted = header("x-ratelimit-remaining") == Some("0");
+    let retry_after = header(RETRY_AFTER.as_str()).and_then(|v| v.trim().parse::<u64>().ok());
+
+    if !exhausted && retry_after.is_none() && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+
+    let reset_at = retry_after
+        .map(|secs| unix_now() + secs)
+        .or_else(|| header("x-ratelimit-reset").and_then(|v| v.trim().parse().ok()));
+    Some(ResolveError::RateLimited { reset_at })
+}
+
+/// One repository's REST API, authenticated when a token is available.
+struct Api<'a> {
+    client: &'a Client,
+    api_url: &'a str,
+    token: Option<String>,
+}
+
+impl<'a> Api<'a> {
+    fn new(client: &'a Client, api_url: &'a str, token: Option<&str>) -> Self {
+        let token = token.map(str::to_string).or_else(|| {
+            TOKEN_ENV_VARS
+                .iter()
+                .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        });
+        Api {
+            client,
+            api_url,
+            token,
+        }
+    }
+
+    fn releases_url(&self, path: &[&str]) -> Result<Url, String> {
+        let mut url = Url::parse(self.api_url)
+            .map_err(|e| format!("invalid API URL {}: {}", self.api_url, e))?;
+        url.path_segments_mut()
+            .map_err(|_| format!("invalid API URL {}", self.api_url))?
+            .push("releases")
+            .extend(path);
+        Ok(url)
+    }
+
+    async fn get(&self, url: Url) -> Result<Response, ResolveError> {
+        let mut request = self
+            .client
+            .get(url.clone())
+            .header("User-Agent", USER_AGENT);
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+
+        let resp = http::send_bounded(request)
+            .await
+            .map_err(|e| request_error(url.as_str(), e))?;
+
+        if let Some(remaining) = resp
+            .headers()
+            .get("x-ratelimit-remaining")
+            .and_then(|v| v.to_str().ok())
+        {
+            println!("[INFO] GitHub API requests remaining: {}", remaining);
+        }
+
+        if let Some(error) = rate_limit_error(resp.status(), resp.headers()) {
+            return Err(error);
+        }
+        if !resp.status().is_success() {
+            return Err(ResolveError::Http {
+                url: url.to_string(),
+                status: resp.status().as_u16(),
+            });
+        }
+
+        Ok(resp)
+    }
+
+    async fn fetch_json<T: DeserializeOwned>(&self, url: Url) -> Result<T, ResolveError> {
+        let body = self
+            .get(url.clone())
+            .await?
+            .text()
+            .await
+            .map_err(|e| format!("failed to read {}: {}", url, e))?;
+        serde_json::from_str(&body)
+            .map_err(|e| ResolveError::MalformedMetadata(format!("{}: {}", url, e)))
+    }
+
+    async fn fetch_release_list(&self) -> Result<Vec<Release>, ResolveError> {
+        let mut url = self.releases_url(&[])?;
+        url.query_pairs_mut()
+            .append_pair("per_page", &RELEASE_LIST_LIMIT.to_string());
+        self.fetch_json(url).await
+    }
+
+    /// Fetches the release selected by `channel`.
+    async fn fetch_release(&self, channel: &ReleaseChannel) -> Result<Release, ResolveError> {
+        let no_release = || ResolveError::NoRelease(channel.clone());
+
+        match channel {
+            ReleaseChannel::Stable => {
+                match self.fetch_json(self.releases_url(&["latest"])?).await {
+                    // `/latest` ignores prereleases, so tell "nothing published"
+                    // apart from "only prereleases published".
+                    Err(ResolveError::Http { status: 404, .. }) => {
+                        let releases = self.fetch_release_list().await?;
+                        Err(
+                            if releases
+                                .iter()
+                                .any(|release| !release.draft && release.prerelease)
+                            {
+                                ResolveError::PrereleaseOnly
+                            } else {
+                                no_release()
+                            },
+                        )
+                    }
+                    result => result,
+                }
+            }
+            ReleaseChannel::Beta => self
+                .fetch_release_list()
+                .await?
+                .into_iter()
+                .find(|release| release.prerelease && !release.draft)
+                .ok_or_else(no_release),
+            ReleaseChannel::Tag(tag) => {
+                match self.fetch_json(self.releases_url(&["tags", tag])?).await {
+                    Err(ResolveError::Http { status: 404, .. }) => Err(no_release()),
+                    result => result,
+                }
+            }
+        }
+    }
+}
+
+/// Lists recent releases that ship an installer for this machine, newest
+/// first.
+pub async fn list_releases(
+    client: &Client,
+    api_url: &str,
+    token: Option<&str>,
+    selector: &AssetSelector,
+) -> Result<Vec<ReleaseSummary>, ResolveError> {
+    let releases = Api::new(client, api_url, token)
+        .fetch_release_list()
+        .await?;
+
+    Ok(releases
+        .into_iter()
+        .filter(|release| !release.draft && release.installer(selector).is_some())
+        .map(|release| ReleaseSummary {
+            name: release.name.unwrap_or_else(|| release.tag_name.clone()),
+            tag: release.tag_name,
+            prerelease: release.prerelease,
+            published_at: release.published_at,
+        })
+        .collect())
+}
+
+/// Resolves the installer through the REST API, falling back to the web
+/// download redirect under `web_url` when the API request itself fails.
+pub async fn resolve(
+    client: &Client,
+    api_url: &str,
+    token: Option<&str>,
+    web_url: Option<&str>,
+    channel: &ReleaseChannel,
+    selector: &AssetSelector,
+) -> Result<ResolvedRelease, ResolveError> {
+    let release = match Api::new(client, api_url, token)
+        .fetch_release(channel)
+        .await
+    {
+        Ok(release) => release,
+        // The API answered; the redirect would not find anything either.
+        Err(e @ (ResolveError::NoRelease(_) | ResolveError::PrereleaseOnly)) => return Err(e),
+        Err(api_error) => {
+            let Some(web_url) = web_url else {
+                return Err(api_error);
+            };
+            println!(
+                "[WARN] GitHub API unavailable ({}), trying download redirect",
+                api_error
+            );
+            return resolve_from_redirect(client, web_url, channel, selector)
+                .await
+                .map_err(|e| {
+                    println!("[WARN] Download redirect fallback failed: {}", e);
+                    api_error
+                });
+        }
+    };
+    println!("[INFO] Selected release {}", release.tag_name);
+
+    let (installer, selected) =
+        release
+            .installer(selector)
+            .ok_or_else(|| ResolveError::NoMatchingAsset {
+                tag: release.tag_name.clone(),
+            })?;
+    println!(
+        "[INFO] Found target installer: {} ({})",
+        selected.name, selected.arch
+    );
+
+    let sha256 = find_sha256(client, installer, &release.assets).await;
+
+    Ok(ResolvedRelease {
+        size: installer.size,
+        sha256,
+        notes: release.body.clone(),
+        ..ResolvedRelease::new(
+            Some(release.tag_name.clone()),
+            selected,
+            installer.browser_download_url.clone(),
+        )
+    })
+}
+
+/// Maps a REST API root (`https://api.github.com/repos/<owner>/<repo>` or
+/// `https://<host>/api/v3/repos/<owner>/<repo>` on GitHub Enterprise) to
+/// the repository's web root.
+pub fn web_url_from_api(api_url: &str) -> Option<String> {
+    let mut url = Url::parse(api_url).ok()?;
+    let segments: Vec<String> = url.path_segments()?.map(str::to_string).collect();
+
+    let repo = match segments.as_slice() {
+        [repos, owner, repo] if repos == "repos" => [owner, repo],
+        [api, v3, repos, owner, repo] if api == "api" && v3 == "v3" && repos == "repos" => {
+            [owner, repo]
+        }
+        _ => return None,
+    };
+    if url.host_str() == Some("api.github.com") {
+        url.set_host(Some("github.com")).ok()?;
+    }
+    url.path_segments_mut().ok()?.clear().extend(repo);
+    Some(url.to_string())
+}
+
+/// Resolves the installer from the `releases/latest/download` redirect,
+/// which is not subject to the API rate limit. Candidate asset names are
+/// probed best first; for the stable channel the tag is read back from
+/// the redirect target.
+async fn resolve_from_redirect(
+    client: &Client,
+    web_url: &str,
+    channel: &ReleaseChannel,
+    selector: &AssetSelector,
+) -> Result<ResolvedRelease, String> {
+    let release_path: &[&str] = match channel {
+        ReleaseChannel::Stable => &["latest", "download"],
+        ReleaseChannel::Beta => return Err("no download redirect for the beta channel".to_string()),
+        ReleaseChannel::Tag(tag) => &["download", tag],
+    };
+    let mut release_url =
+        Url::parse(web_url).map_err(|e| format!("invalid web URL {}: {}", web_url, e))?;
+    release_url
+        .path_segments_mut()
+        .map_err(|_| format!("invalid web URL {}", web_url))?
+        .pop_if_empty()
+        .push("releases")
+        .extend(release_path);
+
+    // The redirect target is the information we are after, so it must not
+    // be followed.
+    let no_redirect = http::no_redirect_client()?;
+
+    for asset in selector.candidates() {
+        let mut url = release_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| format!("invalid web URL {}", web_url))?
+            .push(&asset.name);
+        let resp = http::send_bounded(
+            no_redirect
+                .head(url.clone())
+                .header("User-Agent", USER_AGENT),
+        )
+        .await
+        .map_err(|e| format!("request to {} failed: {}", url, e))?;
+
+        let target = if resp.status().is_redirection() {
+            let location = resp
+                .headers()
+                .get(LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| format!("{} redirected without a Location", url))?;
+            url.join(location)
+                .map_err(|e| format!("invalid redirect target {}: {}", location, e))?
+        } else if resp.status().is_success() {
+            url
+        } else if resp.status() == StatusCode::NOT_FOUND {
+            continue;
+        } else {
+            return Err(format!("{} returned HTTP {}", url, resp.status()));
+        };
+
+        let tag = match channel {
+            ReleaseChannel::Tag(tag) => tag.clone(),
+            _ => tag_from_download_url(&target, &asset.name)
+                .ok_or_else(|| format!("cannot determine release from redirect to {}", target))?,
+        };
+        println!("[INFO] Selected release {} via download redirect", tag);
+
+        let mut sidecar_urls = Vec::new();
+        for name in [format!("{}.sha256", asset.name)]
+            .into_iter()
+            .chain(CHECKSUM_ASSET_NAMES.map(str::to_string))
+        {
+            if let Ok(sidecar) = target.join(&name) {
+                sidecar_urls.push((name, sidecar.to_string()));
+            }
+        }
+        let sha256 = find_sha256_in_sidecars(client, &sidecar_urls, &asset.name).await;
+
+        return Ok(ResolvedRelease {
+            sha256,
+            ..ResolvedRelease::new(Some(tag), asset, target.to_string())
+        });
+    }
+
+    Err(format!("{} has no installer for this machine", release_url))
+}
+
+/// Extracts `<tag>` from `.../releases/download/<tag>/<asset_name>`.
+fn tag_from_download_url(url: &Url, asset_name: &str) -> Option<String> {
+    let segments: Vec<&str> = url.path_segments()?.collect();
+    segments
+        .windows(3)
+        .find(|window| window[0] == "download" && window[2] == asset_name)
+        .map(|window| window[1].to_string())
+}
+
+/// Resolves the published digest of `installer`, preferring GitHub's own
+/// `digest` field over `.sha256` and `SHA256SUMS` sidecar assets.
+async fn find_sha256(client: &Client, installer: &Asset, assets: &[Asset]) -> Option<String> {
+    if let Some(digest) = installer.digest.as_deref().and_then(checksum::parse_sha256) {
+        println!("[INFO] Using digest from release metadata");
+        return Some(digest);
+    }
+
+    let sidecar_name = format!("{}.sha256", installer.name);
+    let sidecars: Vec<(String, String)> = assets
+        .iter()
+        .filter(|asset| {
+            asset.name == sidecar_name || CHECKSUM_ASSET_NAMES.contains(&asset.name.as_str())
+        })
+        .map(|asset| (asset.name.clone(), asset.browser_download_url.clone()))
+        .collect();
+
+    find_sha256_in_sidecars(client, &sidecars, &installer.name).await
+}
+
+/// Looks up the digest of `asset_name` in the given `(name, url)` checksum
+/// files.
+async fn find_sha256_in_sidecars(
+    client: &Client,
+    sidecars: &[(String, String)],
+    asset_name: &str,
+) -> Option<String> {
+    for (name, url) in sidecars {
+        let contents = match fetch_text(client, url).await {
+            Ok(contents) => contents,
+            Err(e) => {
+                println!("[WARN] Failed to fetch checksum file: {}", e);
+                continue;
+            }
+        };
+        if let Some(digest) = checksum::find_in_checksum_file(&contents, asset_name) {
+            println!("[INFO] Using digest from {}", name);
+            return Some(digest);
+        }
+    }
+
+    println!("[WARN] No SHA-256 digest published for {}", asset_name);
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arch::Arch;
+    use crate::release::{default_asset_patterns, INSTALLER_ASSET_NAME};
+    use crate::test_support::{Response, TestServer};
+    use serde_json::json;
+    use std::sync::{Arc, OnceLock};
+
+    const DIGEST: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    struct TestAsset {
+        name: &'static str,
+        digest: Option<String>,
+        body: String,
+    }
+
+    fn asset(name: &'static str) -> TestAsset {
+        TestAsset {
+            name,
+            digest: None,
+            body: String::new(),
+        }
+    }
+
+    struct TestRelease {
+        tag: &'static str,
+        prerelease: bool,
+        assets: Vec<TestAsset>,
+    }
+
+    fn release(tag: &'static str, prerelease: bool, assets: Vec<TestAsset>) -> TestRelease {
+        TestRelease {
+            tag,
+            prerelease,
+            assets,
+        }
+    }
+
+    fn github_server(releases: Vec<TestRelease>) -> TestServer {
+        github_server_with_token(releases, None)
+    }
+
+    /// Serves `releases` (newest first) through the GitHub REST endpoints
+    /// under `/repos/test`, with asset bodies at `/download/<tag>/<name>`.
+    /// API requests are rejected unless they carry `token`, if one is given.
+    fn github_server_with_token(
+        releases: Vec<TestRelease>,
+        token: Option<&'static str>,
+    ) -> TestServer {
+        let base = Arc::new(OnceLock::<String>::new());
+        let base_for_handler = Arc::clone(&base);
+        let server = TestServer::start(move |req| {
+            let base = base_for_handler.get().unwrap();
+            if let Some(token) = token {
+                let expected = format!("Bearer {}", token);
+                if req.path.starts_with("/repos/")
+                    && req.header("Authorization") != Some(expected.as_str())
+                {
+                    return Response::status(401);
+                }
+            }
+            let to_json = |release: &TestRelease| {
+                let assets: Vec<_> = release
+                    .assets
+                    .iter()
+                    .map(|asset| {
+                        json!({
+                            "name": asset.name,
+                            "size": asset.body.len(),
+                            "digest": asset.digest,
+                            "browser_download_url":
+                                format!("{}/download/{}/{}", base, release.tag, asset.name),
+                        })
+                    })
+                    .collect();
+                json!({
+                    "tag_name": release.tag,
+                    "prerelease": release.prerelease,
+                    "draft": false,
+                    "body": format!("Notes for {}", release.tag),
+                    "assets": assets,
+                })
+            };
+
+            let path = req.path.as_str();
+            let found = if path == "/repos/test/releases/latest" {
+                releases.iter().find(|release| !release.prerelease)
+            } else if path.starts_with("/repos/test/releases?") {
+                let list: Vec<_> = releases.iter().map(to_json).collect();
+                return Response::ok(json!(list).to_string());
+            } else if let Some(tag) = path.strip_prefix("/repos/test/releases/tags/") {
+                releases.iter().find(|release| release.tag == tag)
+            } else {
+                let body = releases.iter().find_map(|release| {
+                    release.assets.iter().find_map(|asset| {
+                        (path == format!("/download/{}/{}", release.tag, asset.name))
+                            .then(|| asset.body.clone())
+                    })
+                });
+                return body.map_or_else(|| Response::status(404), Response::ok);
+            };
+
+            found.map_or_else(
+                || Response::status(404),
+                |release| Response::ok(to_json(release).to_string()),
+            )
+        });
+        base.set(server.url("")).unwrap();
+        server
+    }
+
+    async fn resolve_from(
+        server: &TestServer,
+        channel: ReleaseChannel,
+    ) -> Result<ResolvedRelease, ResolveError> {
+        resolve(
+            &Client::new(),
+            &server.url("/repos/test"),
+            None,
+            None,
+            &channel,
+            &AssetSelector::default(),
+        )
+        .await
+    }
+
+    fn arm64_selector() -> AssetSelector {
+        AssetSelector::new(Arch::Aarch64, default_asset_patterns())
+    }
+
+    #[tokio::test]
+    async fn reads_release_metadata_and_digest_field() {
+        let mut installer = asset(INSTALLER_ASSET_NAME);
+        installer.digest = Some(format!("sha256:{}", DIGEST));
+        installer.body = "installer".to_string();
+        let server = github_server(vec![release("v1.0.0", false, vec![installer])]);
+
+        let release = resolve_from(&server, ReleaseChannel::Stable).await.unwrap();
+
+        assert_eq!(
+            release,
+            ResolvedRelease {
+                tag: Some("v1.0.0".to_string()),
+                version: Some("1.0.0".to_string()),
+                asset: SelectedAsset {
+                    name: INSTALLER_ASSET_NAME.to_string(),
+                    arch: Arch::X86_64,
+                },
+                url: server.url("/download/v1.0.0/floorp-windows-x86_64.installer.exe"),
+                size: Some(9),
+                sha256: Some(DIGEST.to_string()),
+                notes: Some("Notes for v1.0.0".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn selects_native_arm64_installer() {
+        let arm64_name = "floorp-windows-aarch64.installer.exe";
+        let mut sums = asset("SHA256SUMS");
+        sums.body = format!(
+            "{}  {}\n{}  {}\n",
+            "00".repeat(32),
+            INSTALLER_ASSET_NAME,
+            DIGEST,
+            arm64_name
+        );
+        let server = github_server(vec![release(
+            "v1.0.0",
+            false,
+            vec![asset(INSTALLER_ASSET_NAME), asset(arm64_name), sums],
+        )]);
+
+        let release = resolve(
+            &Client::new(),
+            &server.url("/repos/test"),
+            None,
+            None,
+            &ReleaseChannel::Stable,
+            &arm64_selector(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(release.asset.arch, Arch::Aarch64);
+        assert!(release.url.ends_with(arm64_name));
+        assert_eq!(release.sha256.as_deref(), Some(DIGEST));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_sha256sums_asset() {
+        let mut sums = asset("SHA256SUMS");
+        sums.body = format!("{}  {}\n", DIGEST, INSTALLER_ASSET_NAME);
+        let server = github_server(vec![release(
+            "v1.0.0",
+            false,
+            vec![asset(INSTALLER_ASSET_NAME), sums],
+        )]);
+
+        let release = resolve_from(&server, ReleaseChannel::Stable).await.unwrap();
+
+        assert_eq!(release.sha256.as_deref(), Some(DIGEST));
+    }
+
+    #[tokio::test]
+    async fn missing_digest_is_reported_as_none() {
+        let server = github_server(vec![release(
+            "v1.0.0",
+            false,
+            vec![asset(INSTALLER_ASSET_NAME)],
+        )]);
+
+        let release = resolve_from(&server, ReleaseChannel::Stable).await.unwrap();
+
+        assert_eq!(release.sha256, None);
+    }
+
+    #[tokio::test]
+    async fn selects_release_by_channel() {
+        let server = github_server(vec![
+            release("v2.0.0-beta.1", true, vec![asset(INSTALLER_ASSET_NAME)]),
+            release("v1.1.0", false, vec![asset(INSTALLER_ASSET_NAME)]),
+            release("v1.0.0", false, vec![asset(INSTALLER_ASSET_NAME)]),
+        ]);
+
+        let stable = resolve_from(&server, ReleaseChannel::Stable).await.unwrap();
+        let beta = resolve_from(&server, ReleaseChannel::Beta).await.unwrap();
+        let pinned = resolve_from(&server, ReleaseChannel::Tag("v1.0.0".to_string()))
+            .await
+            .unwrap();
+
+        assert!(stable.url.contains("/v1.1.0/"));
+        assert!(beta.url.contains("/v2.0.0-beta.1/"));
+        assert!(pinned.url.contains("/v1.0.0/"));
+        let missing = ReleaseChannel::Tag("v9.9.9".to_string());
+        assert_eq!(
+            resolve_from(&server, missing.clone()).await.err(),
+            Some(ResolveError::NoRelease(missing))
+        );
+    }
+
+    #[tokio::test]
+    async fn explains_why_no_installer_was_found() {
+        let server = github_server(vec![
+            release("v2.0.0-beta.1", true, vec![asset(INSTALLER_ASSET_NAME)]),
+            release("v1.0.0-linux", false, vec![asset("floorp-linux.tar.xz")]),
+        ]);
+        let prerelease_only = github_server(vec![release(
+            "v2.0.0-beta.1",
+            true,
+            vec![asset(INSTALLER_ASSET_NAME)],
+        )]);
+        let malformed = TestServer::start(|_| Response::ok(r#"{ "tag_name": 1 }"#));
+
+        assert_eq!(
+            resolve_from(&server, ReleaseChannel::Stable).await.err(),
+            Some(ResolveError::NoMatchingAsset {
+                tag: "v1.0.0-linux".to_string()
+            })
+        );
+        assert_eq!(
+            resolve_from(&prerelease_only, ReleaseChannel::Stable)
+                .await
+                .err(),
+            Some(ResolveError::PrereleaseOnly)
+        );
+        assert!(matches!(
+            resolve_from(&malformed, ReleaseChannel::Stable).await,
+            Err(ResolveError::MalformedMetadata(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn lists_releases_with_installer_asset() {
+        let server = github_server(vec![
+            release("v2.0.0-beta.1", true, vec![asset(INSTALLER_ASSET_NAME)]),
+            release(
+                "v1.1.0-linux",
+                false,
+                vec![asset("floorp-linux-x86_64.tar.xz")],
+            ),
+            release("v1.0.0", false, vec![asset(INSTALLER_ASSET_NAME)]),
+        ]);
+
+        let releases = list_releases(
+            &Client::new(),
+            &server.url("/repos/test"),
+            None,
+            &AssetSelector::default(),
+        )
+        .await
+        .unwrap();
+
+        let tags: Vec<_> = releases
+            .iter()
+            .map(|r| (r.tag.as_str(), r.prerelease))
+            .collect();
+        assert_eq!(tags, vec![("v2.0.0-beta.1", true), ("v1.0.0", false)]);
+    }
+
+    #[tokio::test]
+    async fn reports_rate_limit_with_reset_time() {
+        let server = TestServer::start(|_| {
+            Response::status(403)
+                .with_header("X-RateLimit-Remaining", "0")
+                .with_header("X-RateLimit-Reset", "1735689600")
+        });
+
+        let err = resolve_from(&server, ReleaseChannel::Stable)
+            .await
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            err,
+            ResolveError::RateLimited {
+                reset_at: Some(1735689600)
+            }
+        );
+    }
+
+    #[test]
+    fn distinguishes_rate_limit_from_plain_forbidden() {
+        assert_eq!(
+            rate_limit_error(StatusCode::FORBIDDEN, &HeaderMap::new()),
+            None
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "60".parse().unwrap());
+        let before = unix_now();
+        match rate_limit_error(StatusCode::FORBIDDEN, &headers) {
+            Some(ResolveError::RateLimited {
+                reset_at: Some(reset_at),
+            }) => assert!(reset_at >= before + 60),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_configured_token() {
+        let server = github_server_with_token(
+            vec![release("v1.0.0", false, vec![asset(INSTALLER_ASSET_NAME)])],
+            Some("secret"),
+        );
+        let api_url = server.url("/repos/test");
+
+        let release = resolve(
+            &Client::new(),
+            &api_url,
+            Some("secret"),
+            None,
+            &ReleaseChannel::Stable,
+            &AssetSelector::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(release.url.ends_with(INSTALLER_ASSET_NAME));
+    }
+
+    /// Simulates github.com with the API throttled: the web redirect for the
+    /// latest release points at `v12.3.4`, which also publishes a sidecar.
+    fn throttled_server() -> TestServer {
+        TestServer::start(|req| match req.path.as_str() {
+            path if path.starts_with("/repos/") => Response::status(403)
+                .with_header("X-RateLimit-Remaining", "0")
+                .with_header("X-RateLimit-Reset", "1735689600"),
+            "/test/releases/latest/download/floorp-windows-x86_64.installer.exe" => {
+                Response::status(302).with_header(
+                    "Location",
+                    "/test/releases/download/v12.3.4/floorp-windows-x86_64.installer.exe",
+                )
+            }
+            "/test/releases/download/v12.3.4/floorp-windows-x86_64.installer.exe" => {
+                Response::ok("installer")
+            }
+            "/test/releases/download/v12.3.4/floorp-windows-x86_64.installer.exe.sha256" => {
+                Response::ok(format!("{}  {}\n", DIGEST, INSTALLER_ASSET_NAME))
+            }
+            _ => Response::status(404),
+        })
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_download_redirect_when_api_fails() {
+        let server = throttled_server();
+
+        let release = resolve(
+            &Client::new(),
+            &server.url("/repos/test"),
+            None,
+            Some(&server.url("/test")),
+            &ReleaseChannel::Stable,
+            &arm64_selector(),
+        )
+        .await
+        .unwrap();
+
+        // No ARM64 build is published, so the x64 one is used under emulation.
+        assert_eq!(release.asset.arch, Arch::X86_64);
+        assert_eq!(release.tag.as_deref(), Some("v12.3.4"));
+        assert_eq!(
+            release.url,
+            server.url("/test/releases/download/v12.3.4/floorp-windows-x86_64.installer.exe")
+        );
+        assert_eq!(release.sha256.as_deref(), Some(DIGEST));
+    }
+
+    #[tokio::test]
+    async fn reports_api_error_when_redirect_fallback_also_fails() {
+        let server = throttled_server();
+
+        let err = resolve(
+            &Client::new(),
+            &server.url("/repos/test"),
+            None,
+            Some(&server.url("/test")),
+            &ReleaseChannel::Tag("v0.0.1".to_string()),
+            &AssetSelector::default(),
+        )
+        .await
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            err,
+            ResolveError::RateLimited {
+                reset_at: Some(1735689600)
+            }
+        );
+    }
+
+    #[test]
+    fn derives_web_url_from_api_url() {
+        assert_eq!(
+            web_url_from_api(GITHUB_REPO_API_URL).as_deref(),
+            Some("https://github.com/Floorp-Projects/Floorp")
+        );
+        assert_eq!(
+            web_url_from_api("https://git.corp.example/api/v3/repos/floorp/floorp").as_deref(),
+            Some("https://git.corp.example/floorp/floorp")
+        );
+        assert_eq!(web_url_from_api("https://example.com/floorp"), None);
+    }
+}
@@ -0,0 +1,265 @@
+//! Release lookup through a static JSON manifest.
+//!
+//! The manifest lists releases newest first. Installer URLs may be relative
+//! to the manifest itself, so a mirror can be a plain directory of files:
+//!
+//! ```json
+//! {
+//!   "releases": [
+//!     {
+//!       "tag": "v12.1.0",
+//!       "prerelease": false,
+//!       "publishedAt": "2025-01-01T00:00:00Z",
+//!       "arch": "x86_64",
+//!       "url": "v12.1.0/floorp-windows-x86_64.installer.exe",
+//!       "size": 1048576,
+//!       "sha256": "...",
+//!       "notes": "..."
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! A release built for several architectures is listed once per build;
+//! `arch` defaults to `x86_64`.
+
+use super::{
+    fetch_text, AssetSelector, ReleaseChannel, ReleaseSummary, ResolveError, ResolvedRelease,
+    SelectedAsset,
+};
+use crate::arch::Arch;
+use crate::checksum;
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use std::collections::HashSet;
+
+#[derive(Deserialize)]
+struct Manifest {
+    releases: Vec<ManifestRelease>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestRelease {
+    tag: String,
+    name: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+    published_at: Option<String>,
+    #[serde(default = "default_arch")]
+    arch: Arch,
+    url: String,
+    size: Option<u64>,
+    sha256: Option<String>,
+    notes: Option<String>,
+}
+
+fn default_arch() -> Arch {
+    Arch::X86_64
+}
+
+async fn fetch_manifest(client: &Client, url: &str) -> Result<Manifest, ResolveError> {
+    let body = fetch_text(client, url).await?;
+    serde_json::from_str(&body)
+        .map_err(|e| ResolveError::MalformedMetadata(format!("manifest {}: {}", url, e)))
+}
+
+pub async fn list_releases(
+    client: &Client,
+    url: &str,
+    selector: &AssetSelector,
+) -> Result<Vec<ReleaseSummary>, ResolveError> {
+    let manifest = fetch_manifest(client, url).await?;
+    let mut seen = HashSet::new();
+
+    Ok(manifest
+        .releases
+        .into_iter()
+        .filter(|release| selector.installable().contains(&release.arch))
+        .filter(|release| seen.insert(release.tag.clone()))
+        .map(|release| ReleaseSummary {
+            name: release.name.unwrap_or_else(|| release.tag.clone()),
+            tag: release.tag,
+            prerelease: release.prerelease,
+            published_at: release.published_at,
+        })
+        .collect())
+}
+
+pub async fn resolve(
+    client: &Client,
+    url: &str,
+    channel: &ReleaseChannel,
+    selector: &AssetSelector,
+) -> Result<ResolvedRelease, ResolveError> {
+    let manifest = fetch_manifest(client, url).await?;
+    let has_prerelease = manifest.releases.iter().any(|release| release.prerelease);
+
+    let builds: Vec<ManifestRelease> = manifest
+        .releases
+        .into_iter()
+        .filter(|release| match channel {
+            ReleaseChannel::Stable => !release.prerelease,
+            ReleaseChannel::Beta => release.prerelease,
+            ReleaseChannel::Tag(tag) => release.tag == *tag,
+        })
+        .collect();
+    let tag = builds
+        .first()
+        .map(|release| release.tag.clone())
+        .ok_or_else(|| match channel {
+            ReleaseChannel::Stable if has_prerelease => ResolveError::PrereleaseOnly,
+            _ => ResolveError::NoRelease(channel.clone()),
+        })?;
+    println!("[INFO] Selected release {} from manifest", tag);
+
+    let release = selector
+        .installable()
+        .iter()
+        .find_map(|arch| {
+            builds
+                .iter()
+                .find(|release| release.tag == tag && release.arch == *arch)
+        })
+        .ok_or_else(|| ResolveError::NoMatchingAsset { tag: tag.clone() })?;
+
+    let installer_url = Url::parse(url)
+        .and_then(|base| base.join(&release.url))
+        .map_err(|e| {
+            ResolveError::MalformedMetadata(format!("invalid URL {}: {}", release.url, e))
+        })?;
+
+    let sha256 =
+        match release.sha256.as_deref() {
+            Some(value) => Some(checksum::parse_sha256(value).ok_or_else(|| {
+                ResolveError::MalformedMetadata(format!("invalid sha256 {}", value))
+            })?),
+            None => None,
+        };
+
+    let asset = SelectedAsset {
+        name: installer_url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default()
+            .to_string(),
+        arch: release.arch,
+    };
+
+    Ok(ResolvedRelease {
+        size: release.size,
+        sha256,
+        notes: release.notes.clone(),
+        ..ResolvedRelease::new(Some(tag), asset, installer_url.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::release::default_asset_patterns;
+    use crate::test_support::{Response, TestServer};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn resolves_relative_urls_against_manifest() {
+        let digest = "ab".repeat(32);
+        let manifest = json!({
+            "releases": [
+                { "tag": "v2.0.0-beta.1", "prerelease": true, "url": "beta/installer.exe" },
+                { "tag": "v1.0.0", "url": "v1.0.0/installer.exe", "sha256": digest },
+            ]
+        });
+        let server = TestServer::start(move |_| Response::ok(manifest.to_string()));
+        let url = server.url("/floorp/releases.json");
+
+        let selector = AssetSelector::default();
+
+        let stable = resolve(&Client::new(), &url, &ReleaseChannel::Stable, &selector)
+            .await
+            .unwrap();
+        let beta = resolve(&Client::new(), &url, &ReleaseChannel::Beta, &selector)
+            .await
+            .unwrap();
+
+        assert_eq!(stable.url, server.url("/floorp/v1.0.0/installer.exe"));
+        assert_eq!(stable.sha256, Some(digest));
+        assert_eq!(beta.url, server.url("/floorp/beta/installer.exe"));
+        assert_eq!(beta.sha256, None);
+    }
+
+    #[tokio::test]
+    async fn explains_why_no_release_matched() {
+        let manifest = json!({
+            "releases": [
+                { "tag": "v2.0.0-beta.1", "prerelease": true, "url": "beta/installer.exe" },
+            ]
+        });
+        let server = TestServer::start(move |req| match req.path.as_str() {
+            "/releases.json" => Response::ok(manifest.to_string()),
+            _ => Response::ok("{ \"releases\": {} }"),
+        });
+        let resolve_err = |path, channel| {
+            let url = server.url(path);
+            async move {
+                resolve(&Client::new(), &url, &channel, &AssetSelector::default())
+                    .await
+                    .err()
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(
+            resolve_err("/releases.json", ReleaseChannel::Stable).await,
+            ResolveError::PrereleaseOnly
+        );
+        let tag = ReleaseChannel::Tag("v1.0.0".to_string());
+        assert_eq!(
+            resolve_err("/releases.json", tag.clone()).await,
+            ResolveError::NoRelease(tag)
+        );
+        assert!(matches!(
+            resolve_err("/broken.json", ReleaseChannel::Stable).await,
+            ResolveError::MalformedMetadata(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn picks_build_for_machine_architecture() {
+        let manifest = json!({
+            "releases": [
+                { "tag": "v1.1.0", "url": "v1.1.0/x64.exe" },
+                { "tag": "v1.1.0", "arch": "aarch64", "url": "v1.1.0/arm64.exe" },
+                { "tag": "v1.0.0", "arch": "aarch64", "url": "v1.0.0/arm64.exe" },
+            ]
+        });
+        let server = TestServer::start(move |_| Response::ok(manifest.to_string()));
+        let url = server.url("/releases.json");
+        let arm64 = AssetSelector::new(Arch::Aarch64, default_asset_patterns());
+
+        let native = resolve(&Client::new(), &url, &ReleaseChannel::Stable, &arm64)
+            .await
+            .unwrap();
+        let x64_only = resolve(
+            &Client::new(),
+            &url,
+            &ReleaseChannel::Tag("v1.0.0".to_string()),
+            &AssetSelector::default(),
+        )
+        .await;
+        let listed = list_releases(&Client::new(), &url, &AssetSelector::default())
+            .await
+            .unwrap();
+
+        assert_eq!(native.url, server.url("/v1.1.0/arm64.exe"));
+        assert_eq!(native.asset.arch, Arch::Aarch64);
+        assert_eq!(native.asset.name, "arm64.exe");
+        assert_eq!(
+            x64_only.err(),
+            Some(ResolveError::NoMatchingAsset {
+                tag: "v1.0.0".to_string()
+            })
+        );
+        assert_eq!(listed.len(), 1);
+    }
+}
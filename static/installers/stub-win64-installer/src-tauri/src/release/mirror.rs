@@ -0,0 +1,124 @@
+//! Release lookup on a plain directory mirror.
+//!
+//! The mirror stores each release under `<base>/<tag>/<asset>`, and the
+//! current stable release additionally under `<base>/latest/<asset>`. An
+//! optional `<asset>.sha256` file next to the installer provides the digest.
+
+use super::{
+    fetch_text, request_error, AssetSelector, ReleaseChannel, ResolveError, ResolvedRelease,
+    USER_AGENT,
+};
+use crate::checksum;
+use crate::http;
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::{Client, StatusCode, Url};
+
+/// Probes the selector's candidate asset names, best first, and resolves
+/// the first one the mirror has.
+pub async fn resolve(
+    client: &Client,
+    base_url: &str,
+    channel: &ReleaseChannel,
+    selector: &AssetSelector,
+) -> Result<ResolvedRelease, ResolveError> {
+    let directory = match channel {
+        ReleaseChannel::Stable => "latest",
+        ReleaseChannel::Beta => {
+            return Err("mirror does not provide a beta channel".to_string().into())
+        }
+        ReleaseChannel::Tag(tag) => tag.as_str(),
+    };
+
+    for asset in selector.candidates() {
+        let url = file_url(base_url, directory, &asset.name)?;
+
+        let resp = http::send_bounded(client.head(&url).header("User-Agent", USER_AGENT))
+            .await
+            .map_err(|e| request_error(&url, e))?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            continue;
+        }
+        if !resp.status().is_success() {
+            return Err(ResolveError::Http {
+                url,
+                status: resp.status().as_u16(),
+            });
+        }
+        let size = resp
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+
+        let checksum_url = file_url(base_url, directory, &format!("{}.sha256", asset.name))?;
+        let sha256 = match fetch_text(client, &checksum_url).await {
+            Ok(contents) => checksum::find_in_checksum_file(&contents, &asset.name),
+            Err(e) => {
+                println!("[WARN] No checksum file on mirror: {}", e);
+                None
+            }
+        };
+
+        let tag = match channel {
+            ReleaseChannel::Tag(tag) => Some(tag.clone()),
+            _ => None,
+        };
+
+        return Ok(ResolvedRelease {
+            size,
+            sha256,
+            ..ResolvedRelease::new(tag, asset, url)
+        });
+    }
+
+    Err(format!(
+        "{}/{} has no installer for this machine",
+        base_url.trim_end_matches('/'),
+        directory
+    )
+    .into())
+}
+
+/// `<base>/<directory>/<file>`, with the directory and file name escaped so
+/// that a tag cannot reach outside its directory or add a query.
+fn file_url(base_url: &str, directory: &str, file: &str) -> Result<String, ResolveError> {
+    let invalid = || format!("invalid mirror URL: {}", base_url);
+    let mut url = Url::parse(base_url).map_err(|_| invalid())?;
+    url.path_segments_mut()
+        .map_err(|_| invalid())?
+        .pop_if_empty()
+        .push(directory)
+        .push(file);
+    Ok(url.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Response, TestServer};
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn escapes_the_tag_in_the_path() {
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&paths);
+        let server = TestServer::start(move |req| {
+            seen.lock().unwrap().push(req.path.clone());
+            Response::ok("installer")
+        });
+
+        let channel = ReleaseChannel::Tag("v1/../x?y#z".to_string());
+        let release = resolve(
+            &Client::new(),
+            &server.url("/floorp/"),
+            &channel,
+            &AssetSelector::default(),
+        )
+        .await
+        .unwrap();
+
+        let path = "/floorp/v1%2F..%2Fx%3Fy%23z/floorp-windows-x86_64.installer.exe";
+        assert_eq!(release.url, server.url(path));
+        assert_eq!(paths.lock().unwrap()[0], path);
+    }
+}
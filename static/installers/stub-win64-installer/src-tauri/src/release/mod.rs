@@ -0,0 +1,439 @@
+//! Resolves which installer to download from an ordered list of sources.
+
+mod assets;
+mod github;
+mod manifest;
+mod mirror;
+
+use crate::date::UtcTime;
+use crate::http;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub use assets::{default_asset_patterns, AssetPattern, AssetSelector, SelectedAsset};
+pub use github::GITHUB_REPO_API_URL;
+
+/// The x86_64 installer, published with every Windows release.
+pub const INSTALLER_ASSET_NAME: &str = "floorp-windows-x86_64.installer.exe";
+
+const USER_AGENT: &str = "Floorp-Installer";
+
+/// Which release the stub installs.
+///
+/// Serialized as `"stable"`, `"beta"` or `{ "tag": "v12.0.0" }`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    /// The release GitHub marks as latest.
+    #[default]
+    Stable,
+    /// The newest published prerelease.
+    Beta,
+    /// An exact release tag.
+    Tag(String),
+}
+
+/// A release offered in the version picker.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseSummary {
+    pub tag: String,
+    pub name: String,
+    pub prerelease: bool,
+    pub published_at: Option<String>,
+}
+
+/// The installer a source resolved to, with whatever metadata it publishes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedRelease {
+    /// Release tag, when the source reveals which release it picked.
+    pub tag: Option<String>,
+    /// Version number derived from the tag, without a leading `v`.
+    pub version: Option<String>,
+    pub asset: SelectedAsset,
+    pub url: String,
+    /// Installer size in bytes, if known before downloading.
+    pub size: Option<u64>,
+    /// Expected lowercase hex SHA-256, if the release publishes one.
+    pub sha256: Option<String>,
+    /// Release notes (Markdown), if the source publishes them.
+    pub notes: Option<String>,
+}
+
+impl ResolvedRelease {
+    fn new(tag: Option<String>, asset: SelectedAsset, url: String) -> Self {
+        let version = tag
+            .as_deref()
+            .map(|tag| tag.strip_prefix(['v', 'V']).unwrap_or(tag).to_string());
+        ResolvedRelease {
+            tag,
+            version,
+            asset,
+            url,
+            size: None,
+            sha256: None,
+            notes: None,
+        }
+    }
+}
+
+/// Where release information can be obtained from.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ReleaseSource {
+    /// The GitHub REST API of a repository publishing Floorp releases.
+    Github {
+        #[serde(default = "default_github_api_url")]
+        api_url: String,
+        /// API token used to raise the rate limit; falls back to the
+        /// `FLOORP_INSTALLER_GITHUB_TOKEN` and `GITHUB_TOKEN` variables.
+        #[serde(default)]
+        token: Option<String>,
+        /// Web root of the repository, used for the
+        /// `releases/latest/download` fallback when the API fails. Derived
+        /// from `apiUrl` when unset.
+        #[serde(default)]
+        web_url: Option<String>,
+    },
+    /// A static JSON manifest listing releases, see the `manifest` module.
+    Manifest { url: String },
+    /// A directory mirror laid out as `<baseUrl>/<tag>/<asset>`, with the
+    /// current stable release under `latest`.
+    Mirror { base_url: String },
+}
+
+fn default_github_api_url() -> String {
+    GITHUB_REPO_API_URL.to_string()
+}
+
+impl Default for ReleaseSource {
+    fn default() -> Self {
+        ReleaseSource::Github {
+            api_url: default_github_api_url(),
+            token: None,
+            web_url: None,
+        }
+    }
+}
+
+impl fmt::Display for ReleaseSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReleaseSource::Github { api_url, .. } => write!(f, "GitHub ({})", api_url),
+            ReleaseSource::Manifest { url } => write!(f, "manifest ({})", url),
+            ReleaseSource::Mirror { base_url } => write!(f, "mirror ({})", base_url),
+        }
+    }
+}
+
+/// Why a release lookup failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolveError {
+    /// The source throttled us; `reset_at` is when the limit lifts, in
+    /// seconds since the Unix epoch, if the source said.
+    RateLimited {
+        reset_at: Option<u64>,
+    },
+    /// The source answered with an unexpected HTTP status.
+    Http {
+        url: String,
+        status: u16,
+    },
+    /// The source's release metadata could not be parsed.
+    MalformedMetadata(String),
+    /// No release matches the requested channel.
+    NoRelease(ReleaseChannel),
+    /// A stable release was requested, but only prereleases are published.
+    PrereleaseOnly,
+    /// The selected release does not ship the Windows installer.
+    NoMatchingAsset {
+        tag: String,
+    },
+    /// The TLS connection failed, see [`crate::http::tls_problem`].
+    Tls(String),
+    Failed(String),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::RateLimited {
+                reset_at: Some(reset_at),
+            } => write!(f, "rate limited until {}", format_utc(*reset_at)),
+            ResolveError::RateLimited { reset_at: None } => write!(f, "rate limited"),
+            ResolveError::Http { url, status } => write!(f, "{} returned HTTP {}", url, status),
+            ResolveError::MalformedMetadata(reason) => {
+                write!(f, "malformed release metadata: {}", reason)
+            }
+            ResolveError::NoRelease(channel) => write!(f, "no {:?} release published", channel),
+            ResolveError::PrereleaseOnly => write!(f, "only prereleases are published"),
+            ResolveError::NoMatchingAsset { tag } => {
+                write!(f, "release {} has no installer for this machine", tag)
+            }
+            ResolveError::Tls(problem) => write!(f, "TLS connection failed: {}", problem),
+            ResolveError::Failed(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl From<String> for ResolveError {
+    fn from(reason: String) -> Self {
+        ResolveError::Failed(reason)
+    }
+}
+
+/// Why a single source could not provide the installer.
+#[derive(Clone, Debug)]
+pub struct SourceFailure {
+    pub source: String,
+    pub error: ResolveError,
+}
+
+impl fmt::Display for SourceFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.error)
+    }
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM UTC`.
+pub fn format_utc(secs: u64) -> String {
+    let time = UtcTime::from_unix(secs as i64);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        time.year, time.month, time.day, time.hour, time.minute
+    )
+}
+
+impl ReleaseSource {
+    async fn resolve(
+        &self,
+        client: &Client,
+        channel: &ReleaseChannel,
+        selector: &AssetSelector,
+    ) -> Result<ResolvedRelease, ResolveError> {
+        match self {
+            ReleaseSource::Github {
+                api_url,
+                token,
+                web_url,
+            } => {
+                let web_url = web_url
+                    .clone()
+                    .or_else(|| github::web_url_from_api(api_url));
+                github::resolve(
+                    client,
+                    api_url,
+                    token.as_deref(),
+                    web_url.as_deref(),
+                    channel,
+                    selector,
+                )
+                .await
+            }
+            ReleaseSource::Manifest { url } => {
+                manifest::resolve(client, url, channel, selector).await
+            }
+            ReleaseSource::Mirror { base_url } => {
+                mirror::resolve(client, base_url, channel, selector).await
+            }
+        }
+    }
+
+    async fn list(
+        &self,
+        client: &Client,
+        selector: &AssetSelector,
+    ) -> Result<Vec<ReleaseSummary>, ResolveError> {
+        match self {
+            ReleaseSource::Github { api_url, token, .. } => {
+                github::list_releases(client, api_url, token.as_deref(), selector).await
+            }
+            ReleaseSource::Manifest { url } => manifest::list_releases(client, url, selector).await,
+            ReleaseSource::Mirror { .. } => {
+                Err("mirrors do not publish a release list".to_string().into())
+            }
+        }
+    }
+}
+
+/// Tries each source in order and returns the first installer found,
+/// or the reason every source failed.
+pub async fn resolve_installer(
+    client: &Client,
+    sources: &[ReleaseSource],
+    channel: &ReleaseChannel,
+    selector: &AssetSelector,
+) -> Result<ResolvedRelease, Vec<SourceFailure>> {
+    let mut failures = Vec::new();
+
+    for source in sources {
+        println!("[INFO] Resolving {:?} release from {}", channel, source);
+        match source.resolve(client, channel, selector).await {
+            Ok(release) => return Ok(release),
+            Err(error) => {
+                println!("[WARN] Release source {} failed: {}", source, error);
+                failures.push(SourceFailure {
+                    source: source.to_string(),
+                    error,
+                });
+            }
+        }
+    }
+
+    Err(failures)
+}
+
+/// Lists available releases from the first source that can provide them.
+pub async fn list_releases(
+    client: &Client,
+    sources: &[ReleaseSource],
+    selector: &AssetSelector,
+) -> Result<Vec<ReleaseSummary>, Vec<SourceFailure>> {
+    let mut failures = Vec::new();
+
+    for source in sources {
+        match source.list(client, selector).await {
+            Ok(releases) => return Ok(releases),
+            Err(error) => {
+                println!("[WARN] Release source {} failed: {}", source, error);
+                failures.push(SourceFailure {
+                    source: source.to_string(),
+                    error,
+                });
+            }
+        }
+    }
+
+    Err(failures)
+}
+
+/// Maps a failed request, setting TLS failures apart.
+fn request_error(url: &str, err: http::SendError) -> ResolveError {
+    match err.tls_problem() {
+        Some(problem) => ResolveError::Tls(problem),
+        None => ResolveError::Failed(format!("request to {} failed: {}", url, err)),
+    }
+}
+
+async fn fetch_text(client: &Client, url: &str) -> Result<String, ResolveError> {
+    let text = http::send_bounded(client.get(url).header("User-Agent", USER_AGENT))
+        .await
+        .map_err(|e| request_error(url, e))?
+        .error_for_status()
+        .map_err(|e| format!("request to {} failed: {}", url, e))?
+        .text()
+        .await
+        .map_err(|e| format!("failed to read {}: {}", url, e))?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Response, TestServer};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn fails_over_to_next_source_and_records_reasons() {
+        let github = TestServer::start(|_| Response::status(503));
+        let manifest = TestServer::start(|_| Response::status(404));
+        let mirror = TestServer::start(|req| match req.path.as_str() {
+            "/floorp/latest/floorp-windows-x86_64.installer.exe" => Response::ok("installer"),
+            _ => Response::status(404),
+        });
+
+        let sources = vec![
+            ReleaseSource::Github {
+                api_url: github.url("/repos/test"),
+                token: None,
+                web_url: None,
+            },
+            ReleaseSource::Manifest {
+                url: manifest.url("/releases.json"),
+            },
+            ReleaseSource::Mirror {
+                base_url: mirror.url("/floorp"),
+            },
+        ];
+
+        let selector = AssetSelector::default();
+        let release =
+            resolve_installer(&Client::new(), &sources, &ReleaseChannel::Stable, &selector)
+                .await
+                .unwrap();
+        assert_eq!(
+            release.url,
+            mirror.url("/floorp/latest/floorp-windows-x86_64.installer.exe")
+        );
+
+        let failures = resolve_installer(
+            &Client::new(),
+            &sources[..2],
+            &ReleaseChannel::Stable,
+            &selector,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(failures.len(), 2);
+        assert!(failures[0].source.starts_with("GitHub"));
+        assert!(
+            failures[0].error.to_string().contains("503"),
+            "{}",
+            failures[0]
+        );
+        assert!(failures[1].source.starts_with("manifest"));
+        assert!(
+            failures[1].error.to_string().contains("404"),
+            "{}",
+            failures[1]
+        );
+    }
+
+    #[test]
+    fn sources_deserialize_from_config() {
+        let sources: Vec<ReleaseSource> = serde_json::from_value(json!([
+            { "type": "github" },
+            { "type": "manifest", "url": "https://mirror.example/floorp.json" },
+            { "type": "mirror", "baseUrl": "https://files.corp.example/floorp" },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            sources,
+            vec![
+                ReleaseSource::default(),
+                ReleaseSource::Manifest {
+                    url: "https://mirror.example/floorp.json".to_string()
+                },
+                ReleaseSource::Mirror {
+                    base_url: "https://files.corp.example/floorp".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_reset_time_as_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_utc(1_735_689_600), "2025-01-01 00:00 UTC");
+        assert_eq!(format_utc(1_709_210_096), "2024-02-29 12:34 UTC");
+    }
+
+    #[test]
+    fn channel_deserializes_from_frontend_values() {
+        let parse = |value| serde_json::from_value::<ReleaseChannel>(value).unwrap();
+
+        assert_eq!(parse(json!("stable")), ReleaseChannel::Stable);
+        assert_eq!(parse(json!("beta")), ReleaseChannel::Beta);
+        assert_eq!(
+            parse(json!({ "tag": "v12.0.0" })),
+            ReleaseChannel::Tag("v12.0.0".to_string())
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Checking the Authenticode signature of a PE image without Windows.
+//!
+//! The digest of the image is recomputed and compared with the signed one,
+//! the signer's signature over it is checked against the signer's
+//! certificate, and the certificates that came with the signature are
+//! ordered into a chain whose links are checked as well. The signer's
+//! certificate must be for code signing, every certificate above it a CA,
+//! and the signer's certificate must have been valid when the signature
+//! was made: at the
+//! time of its timestamp, or now if it has none; a timestamp only counts if
+//! its authority chains to one of the trusted roots. Whether the chain is
+//! trusted, and whether it was revoked, is decided by pinning and on
+//! Windows by `WinVerifyTrust`.
+
+use super::cert::{Certificate, CODE_SIGNING};
+use super::der::{self, DerError, Reader};
+use super::hash::HashAlgorithm;
+use super::pe::Layout;
+use super::pkcs7::{hash_algorithm, SignedData};
+use super::timestamp::{self, Timestamp};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek};
+use std::path::Path;
+
+const SPC_INDIRECT_DATA: &str = "1.3.6.1.4.1.311.2.1.4";
+
+/// A signature that checked out.
+#[derive(Clone, Debug)]
+pub struct Signature {
+    /// Algorithm of the signed digest of the image.
+    pub algorithm: HashAlgorithm,
+    /// The signer's certificate first, then its issuers as far as the
+    /// signature includes them.
+    pub chain: Vec<Certificate>,
+    pub timestamp: Option<Timestamp>,
+}
+
+#[derive(Debug)]
+pub enum SignatureError {
+    /// The image has no signature.
+    Unsigned,
+    Malformed(String),
+    UnsupportedAlgorithm(String),
+    /// The image was changed after it was signed.
+    DigestMismatch,
+    /// The signature was not made with the key of the signer's certificate.
+    BadSignature,
+    /// The signer's certificate is not included in the signature.
+    SignerNotFound,
+    /// A certificate of the chain is not signed by the one above it.
+    BrokenChain {
+        subject: String,
+    },
+    /// A certificate of the chain is issued by one that is not a CA.
+    IssuerNotCa {
+        subject: String,
+    },
+    /// The signer's certificate is not for code signing.
+    NotForCodeSigning {
+        subject: String,
+    },
+    /// The timestamp is malformed, does not verify or is not about this
+    /// signature.
+    TimestampInvalid(String),
+    /// The timestamp is outside the validity of the signer's certificate.
+    TimestampOutsideValidity {
+        time: i64,
+        not_before: i64,
+        not_after: i64,
+    },
+    /// There is no timestamp and the signer's certificate is not valid now.
+    CertificateExpired {
+        not_before: i64,
+        not_after: i64,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Unsigned => f.write_str("the file is not signed"),
+            SignatureError::Malformed(reason) => write!(f, "malformed signature: {}", reason),
+            SignatureError::UnsupportedAlgorithm(algorithm) => {
+                write!(f, "unsupported algorithm: {}", algorithm)
+            }
+            SignatureError::DigestMismatch => {
+                f.write_str("the file was modified after it was signed")
+            }
+            SignatureError::BadSignature => {
+                f.write_str("the signature does not match the signer's certificate")
+            }
+            SignatureError::SignerNotFound => {
+                f.write_str("the signer's certificate is missing from the signature")
+            }
+            SignatureError::BrokenChain { subject } => {
+                write!(
+                    f,
+                    "the certificate of {} is not signed by its issuer",
+                    subject
+                )
+            }
+            SignatureError::IssuerNotCa { subject } => {
+                write!(
+                    f,
+                    "the certificate of {} is issued by one that is not a CA",
+                    subject
+                )
+            }
+            SignatureError::NotForCodeSigning { subject } => {
+                write!(f, "the certificate of {} is not for code signing", subject)
+            }
+            SignatureError::TimestampInvalid(reason) => {
+                write!(f, "invalid timestamp: {}", reason)
+            }
+            SignatureError::TimestampOutsideValidity {
+                time,
+                not_before,
+                not_after,
+            } => write!(
+                f,
+                "timestamped {}, outside the certificate's validity from {} to {}",
+                der::format_time(*time),
+                der::format_time(*not_before),
+                der::format_time(*not_after)
+            ),
+            SignatureError::CertificateExpired {
+                not_before,
+                not_after,
+            } => write!(
+                f,
+                "the certificate is only valid from {} to {} and the signature has no timestamp",
+                der::format_time(*not_before),
+                der::format_time(*not_after)
+            ),
+            SignatureError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<DerError> for SignatureError {
+    fn from(e: DerError) -> Self {
+        SignatureError::Malformed(e.0)
+    }
+}
+
+impl From<io::Error> for SignatureError {
+    fn from(e: io::Error) -> Self {
+        SignatureError::Io(e)
+    }
+}
+
+/// Checks the signature of the image at `path` as of Unix time `now`,
+/// accepting timestamps from authorities that chain to one of `roots`.
+pub fn verify_file(
+    path: &Path,
+    now: i64,
+    roots: &[Certificate],
+) -> Result<Signature, SignatureError> {
+    verify(&mut BufReader::new(File::open(path)?), now, roots)
+}
+
+pub fn verify<R: Read + Seek>(
+    reader: &mut R,
+    now: i64,
+    roots: &[Certificate],
+) -> Result<Signature, SignatureError> {
+    let layout = Layout::read(reader)?;
+    let signed = SignedData::parse(&layout.signature(reader)?)?;
+    if signed.content_type != SPC_INDIRECT_DATA {
+        return Err(SignatureError::Malformed(
+            "not an Authenticode signature".to_string(),
+        ));
+    }
+
+    let (algorithm, image_digest) = signed_image_digest(&signed.content)?;
+    if layout.digest(reader, algorithm)? != image_digest {
+        return Err(SignatureError::DigestMismatch);
+    }
+    let leaf = signed.verify_signer()?;
+    if !leaf.has_extended_key_usage(CODE_SIGNING) {
+        return Err(SignatureError::NotForCodeSigning {
+            subject: leaf.subject.to_string(),
+        });
+    }
+    let chain = signed.chain(leaf)?;
+
+    let timestamp = timestamp::verify(&signed.signer, roots)?;
+    let signed_at = timestamp.as_ref().map_or(now, |timestamp| timestamp.time);
+    if signed_at < leaf.not_before || signed_at > leaf.not_after {
+        return Err(match &timestamp {
+            Some(timestamp) => SignatureError::TimestampOutsideValidity {
+                time: timestamp.time,
+                not_before: leaf.not_before,
+                not_after: leaf.not_after,
+            },
+            None => SignatureError::CertificateExpired {
+                not_before: leaf.not_before,
+                not_after: leaf.not_after,
+            },
+        });
+    }
+
+    Ok(Signature {
+        algorithm,
+        chain,
+        timestamp,
+    })
+}
+
+/// The algorithm and value of the image digest in an
+/// `SpcIndirectDataContent`.
+fn signed_image_digest(content: &[u8]) -> Result<(HashAlgorithm, Vec<u8>), SignatureError> {
+    let mut fields = Reader::new(content);
+    fields.expect(der::SEQUENCE)?; // SpcAttributeTypeAndValue
+    let mut digest_info = fields.expect(der::SEQUENCE)?.contents();
+    let algorithm = hash_algorithm(&digest_info.expect(der::SEQUENCE)?)?;
+    Ok((
+        algorithm,
+        digest_info.expect(der::OCTET_STRING)?.value.to_vec(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::fixtures;
+    use std::io::Cursor;
+
+    /// 2025-09-01, while the fixture leaf is valid.
+    const NOW: i64 = 1_756_684_800;
+    /// 2027-01-01, after it expired.
+    const LATER: i64 = 1_798_761_600;
+
+    fn check(image: &[u8]) -> Result<Signature, SignatureError> {
+        verify(&mut Cursor::new(image), NOW, &[fixtures::root()])
+    }
+
+    #[test]
+    fn verifies_a_signed_image_and_its_chain() {
+        let signature = check(&fixtures::signed_image()).unwrap();
+        assert_eq!(signature.algorithm, HashAlgorithm::Sha256);
+        let names: Vec<_> = signature
+            .chain
+            .iter()
+            .map(|cert| cert.subject.common_name().unwrap())
+            .collect();
+        assert_eq!(names, ["SignPath Foundation", "Fixture Code Signing CA"]);
+
+        // The checksum is not covered by the signature.
+        let mut image = fixtures::signed_image();
+        image[fixtures::IMAGE_CHECKSUM_OFFSET] ^= 0xff;
+        assert!(check(&image).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_tampered_image() {
+        let mut image = fixtures::signed_image();
+        image[fixtures::IMAGE_CODE_OFFSET] ^= 0xff;
+        assert!(matches!(check(&image), Err(SignatureError::DigestMismatch)));
+
+        let mut image = fixtures::signed_image();
+        let signature = {
+            let mut reader = Cursor::new(&image);
+            let layout = Layout::read(&mut reader).unwrap();
+            SignedData::parse(&layout.signature(&mut reader).unwrap())
+                .unwrap()
+                .signer
+                .signature
+        };
+        let at = image
+            .windows(signature.len())
+            .position(|window| window == signature)
+            .unwrap();
+        image[at + signature.len() - 1] ^= 0x01;
+        assert!(matches!(check(&image), Err(SignatureError::BadSignature)));
+    }
+
+    #[test]
+    fn checks_the_signing_time_against_the_certificate() {
+        // Timestamped while the leaf was valid, so still good after it expired.
+        let signature = verify(
+            &mut Cursor::new(fixtures::signed_image()),
+            LATER,
+            &[fixtures::root()],
+        )
+        .unwrap();
+        assert_eq!(
+            signature.timestamp,
+            Some(Timestamp {
+                time: 1_748_779_200,
+                authority: "CN=Fixture Timestamp Authority, O=Fixture Trust".to_string(),
+            })
+        );
+
+        assert!(matches!(
+            check(&fixtures::late_timestamp_image()),
+            Err(SignatureError::TimestampOutsideValidity {
+                time: 1_772_323_200,
+                ..
+            })
+        ));
+
+        let untimestamped = fixtures::untimestamped_image();
+        assert!(check(&untimestamped).unwrap().timestamp.is_none());
+        assert!(matches!(
+            verify(&mut Cursor::new(untimestamped), LATER, &[fixtures::root()]),
+            Err(SignatureError::CertificateExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_a_timestamp_that_does_not_verify() {
+        let mut image = fixtures::signed_image();
+        let time = b"20250601120000Z";
+        let at = image
+            .windows(time.len())
+            .position(|window| window == time)
+            .unwrap();
+        image[at + 13] = b'1';
+        assert!(matches!(
+            check(&image),
+            Err(SignatureError::TimestampInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_a_timestamp_authority_without_a_trusted_root() {
+        let untrusted = |result| match result {
+            Err(SignatureError::TimestampInvalid(reason)) => {
+                reason == "the timestamp authority does not chain to a trusted root"
+            }
+            _ => false,
+        };
+
+        assert!(untrusted(check(&fixtures::rogue_timestamp_image())));
+        assert!(untrusted(verify(
+            &mut Cursor::new(fixtures::signed_image()),
+            NOW,
+            &[fixtures::other_root()],
+        )));
+    }
+
+    #[test]
+    fn rejects_a_timestamp_authority_without_the_time_stamping_usage() {
+        assert!(matches!(
+            check(&fixtures::leaf_timestamp_image()),
+            Err(SignatureError::TimestampInvalid(reason))
+                if reason == "the timestamp authority's certificate is not for time stamping"
+        ));
+    }
+
+    #[test]
+    fn rejects_a_chain_through_a_certificate_that_is_not_a_ca() {
+        assert!(matches!(
+            check(&fixtures::minted_image()),
+            Err(SignatureError::IssuerNotCa { subject })
+                if subject == "CN=Open Source Developer, Ryosuke Asano"
+        ));
+    }
+
+    #[test]
+    fn rejects_a_signer_that_is_not_for_code_signing() {
+        assert!(matches!(
+            check(&fixtures::tsa_signed_image()),
+            Err(SignatureError::NotForCodeSigning { subject })
+                if subject == "CN=Fixture Timestamp Authority, O=Fixture Trust"
+        ));
+    }
+
+    #[test]
+    fn reports_an_unsigned_image() {
+        assert!(matches!(
+            check(&fixtures::unsigned_image()),
+            Err(SignatureError::Unsigned)
+        ));
+        assert!(matches!(
+            check(b"#!/bin/sh\necho not an image\n".repeat(4).as_slice()),
+            Err(SignatureError::Malformed(_))
+        ));
+
+        // Data appended after the signature is not covered by it.
+        let mut image = fixtures::signed_image();
+        image.extend_from_slice(b"appended");
+        assert!(matches!(check(&image), Err(SignatureError::Malformed(_))));
+    }
+}
@@ -0,0 +1,380 @@
+//! The parts of an X.509 certificate that signer pinning and signature
+//! checks look at.
+
+use super::authenticode::SignatureError;
+use super::der::{self, DerError, Reader};
+use super::hash::HashAlgorithm;
+use super::rsa::PublicKey;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+const COMMON_NAME: &str = "2.5.4.3";
+const BASIC_CONSTRAINTS: &str = "2.5.29.19";
+const EXTENDED_KEY_USAGE: &str = "2.5.29.37";
+
+/// Extended key usage of certificates that sign code.
+pub const CODE_SIGNING: &str = "1.3.6.1.5.5.7.3.3";
+/// Extended key usage of timestamp authorities.
+pub const TIME_STAMPING: &str = "1.3.6.1.5.5.7.3.8";
+
+/// Short names of the attributes commonly found in distinguished names.
+const ATTRIBUTE_NAMES: [(&str, &str); 7] = [
+    (COMMON_NAME, "CN"),
+    ("2.5.4.6", "C"),
+    ("2.5.4.7", "L"),
+    ("2.5.4.8", "S"),
+    ("2.5.4.10", "O"),
+    ("2.5.4.11", "OU"),
+    ("2.5.4.5", "SERIALNUMBER"),
+];
+
+/// A distinguished name, compared by its DER encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Name {
+    der: Vec<u8>,
+    attributes: Vec<(String, String)>,
+}
+
+impl Name {
+    pub(super) fn parse(tlv: &der::Tlv) -> Result<Self, DerError> {
+        let mut attributes = Vec::new();
+        let mut rdns = tlv.contents();
+        while !rdns.is_empty() {
+            let mut rdn = rdns.expect(der::SET)?.contents();
+            while !rdn.is_empty() {
+                let mut attribute = rdn.expect(der::SEQUENCE)?.contents();
+                let oid = der::oid_string(attribute.expect(der::OID)?.value)?;
+                let value = der::string(&attribute.read()?)?;
+                attributes.push((oid, value));
+            }
+        }
+        Ok(Name {
+            der: tlv.raw.to_vec(),
+            attributes,
+        })
+    }
+
+    pub fn common_name(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(oid, _)| oid == COMMON_NAME)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .attributes
+            .iter()
+            .map(|(oid, value)| {
+                let key = ATTRIBUTE_NAMES
+                    .iter()
+                    .find(|(known, _)| known == oid)
+                    .map_or(oid.as_str(), |(_, short)| short);
+                format!("{}={}", key, value)
+            })
+            .collect();
+        f.write_str(&parts.join(", "))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Certificate {
+    der: Vec<u8>,
+    pub serial: Vec<u8>,
+    pub issuer: Name,
+    pub subject: Name,
+    /// Start of the validity period, in Unix seconds.
+    pub not_before: i64,
+    /// End of the validity period, in Unix seconds.
+    pub not_after: i64,
+    spki: Vec<u8>,
+    /// Whether basic constraints make this a CA, which may issue others.
+    pub is_ca: bool,
+    extended_key_usage: Vec<String>,
+    /// The signed part, `tbsCertificate`, with the algorithm and value of
+    /// the issuer's signature over it.
+    tbs: Vec<u8>,
+    signature_algorithm: String,
+    signature: Vec<u8>,
+}
+
+impl Certificate {
+    pub fn from_der(data: &[u8]) -> Result<Self, DerError> {
+        let mut outer = Reader::new(data);
+        let certificate = outer.expect(der::SEQUENCE)?;
+        if !outer.is_empty() {
+            return Err(DerError("trailing data after certificate".to_string()));
+        }
+        let mut fields = certificate.contents();
+        let tbs_tlv = fields.expect(der::SEQUENCE)?;
+        let mut algorithm = fields.expect(der::SEQUENCE)?.contents();
+        let signature_algorithm = der::oid_string(algorithm.expect(der::OID)?.value)?;
+        let signature = match fields.expect(der::BIT_STRING)?.value.split_first() {
+            Some((0, signature)) => signature.to_vec(),
+            _ => return Err(DerError("invalid certificate signature".to_string())),
+        };
+        let mut tbs = tbs_tlv.contents();
+
+        tbs.optional(der::context(0))?; // version
+        let serial = tbs.expect(der::INTEGER)?.value.to_vec();
+        tbs.expect(der::SEQUENCE)?; // signature algorithm
+        let issuer = Name::parse(&tbs.expect(der::SEQUENCE)?)?;
+        let mut validity = tbs.expect(der::SEQUENCE)?.contents();
+        let not_before = der::time(&validity.read()?)?;
+        let not_after = der::time(&validity.read()?)?;
+        let subject = Name::parse(&tbs.expect(der::SEQUENCE)?)?;
+        let spki = tbs.expect(der::SEQUENCE)?.raw.to_vec();
+        tbs.optional(0x81)?; // issuer unique ID
+        tbs.optional(0x82)?; // subject unique ID
+
+        let mut is_ca = false;
+        let mut extended_key_usage = Vec::new();
+        if let Some(extensions) = tbs.optional(der::context(3))? {
+            let mut extensions = extensions.contents().expect(der::SEQUENCE)?.contents();
+            while !extensions.is_empty() {
+                let mut extension = extensions.expect(der::SEQUENCE)?.contents();
+                let oid = der::oid_string(extension.expect(der::OID)?.value)?;
+                extension.optional(der::BOOLEAN)?; // critical
+                let value = extension.expect(der::OCTET_STRING)?.value;
+                match oid.as_str() {
+                    BASIC_CONSTRAINTS => {
+                        let mut constraints = Reader::new(value).expect(der::SEQUENCE)?.contents();
+                        is_ca = constraints
+                            .optional(der::BOOLEAN)?
+                            .is_some_and(|ca| ca.value != [0]);
+                    }
+                    EXTENDED_KEY_USAGE => {
+                        let mut usages = Reader::new(value).expect(der::SEQUENCE)?.contents();
+                        while !usages.is_empty() {
+                            extended_key_usage
+                                .push(der::oid_string(usages.expect(der::OID)?.value)?);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Certificate {
+            der: data.to_vec(),
+            serial,
+            issuer,
+            subject,
+            not_before,
+            not_after,
+            spki,
+            is_ca,
+            extended_key_usage,
+            tbs: tbs_tlv.raw.to_vec(),
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Lowercase hex SHA-256 of the whole certificate.
+    pub fn thumbprint(&self) -> String {
+        hex::encode(Sha256::digest(&self.der))
+    }
+
+    /// Lowercase hex SHA-256 of the SubjectPublicKeyInfo, which stays the
+    /// same when a certificate is renewed with the same key.
+    pub fn spki_sha256(&self) -> String {
+        hex::encode(Sha256::digest(&self.spki))
+    }
+
+    /// Whether the extended key usage of this certificate lists `usage`. A
+    /// certificate without one is not taken to be good for anything.
+    pub fn has_extended_key_usage(&self, usage: &str) -> bool {
+        self.extended_key_usage.iter().any(|listed| listed == usage)
+    }
+
+    /// Whether `issuer` is named as the issuer of this certificate. The
+    /// signature itself is not checked.
+    pub fn names_issuer(&self, issuer: &Certificate) -> bool {
+        self.issuer == issuer.subject
+    }
+
+    /// The RSA key of this certificate; keys of other types are reported
+    /// as unsupported.
+    pub(super) fn public_key(&self) -> Result<PublicKey, SignatureError> {
+        match PublicKey::from_spki(&self.spki)? {
+            Some(key) => Ok(key),
+            None => Err(SignatureError::UnsupportedAlgorithm(
+                "non-RSA public key".to_string(),
+            )),
+        }
+    }
+
+    /// Whether `issuer`'s key made the signature on this certificate.
+    pub fn is_signed_by(&self, issuer: &Certificate) -> Result<bool, SignatureError> {
+        let algorithm = HashAlgorithm::from_rsa_signature_oid(&self.signature_algorithm)
+            .ok_or_else(|| {
+                SignatureError::UnsupportedAlgorithm(self.signature_algorithm.clone())
+            })?;
+        Ok(issuer
+            .public_key()?
+            .verify(algorithm, &algorithm.digest(&self.tbs), &self.signature))
+    }
+}
+
+/// Orders `pool` into the chain from `leaf` upwards, following issuer
+/// names until no further certificate in the pool matches.
+pub fn build_chain(leaf: Certificate, pool: &[Certificate]) -> Vec<Certificate> {
+    let mut chain = vec![leaf];
+    loop {
+        let last = chain.last().unwrap();
+        if last.issuer == last.subject {
+            break;
+        }
+        let next = pool
+            .iter()
+            .find(|cert| last.names_issuer(cert) && !chain.iter().any(|c| c.der == cert.der));
+        match next {
+            Some(cert) => chain.push(cert.clone()),
+            None => break,
+        }
+    }
+    chain
+}
+
+/// Adds the root that signed the top of `chain`, which signatures usually
+/// leave out, from the trusted `roots`. A root is only taken if its key
+/// verifies that signature, so a lookalike name does not get one added.
+pub fn complete_chain(mut chain: Vec<Certificate>, roots: &[Certificate]) -> Vec<Certificate> {
+    let Some(top) = chain.last() else {
+        return chain;
+    };
+    if top.issuer == top.subject {
+        return chain;
+    }
+    let root = roots
+        .iter()
+        .find(|root| top.names_issuer(root) && top.is_signed_by(root).unwrap_or(false));
+    if let Some(root) = root {
+        chain.push(root.clone());
+    }
+    chain
+}
+
+/// The certificates of the Windows root store, for [`complete_chain`].
+#[cfg(windows)]
+pub fn system_roots() -> Vec<Certificate> {
+    use windows::core::w;
+    use windows::Win32::Security::Cryptography::{
+        CertCloseStore, CertEnumCertificatesInStore, CertOpenSystemStoreW, HCRYPTPROV_LEGACY,
+    };
+
+    let mut roots = Vec::new();
+    let store = match unsafe { CertOpenSystemStoreW(HCRYPTPROV_LEGACY::default(), w!("ROOT")) } {
+        Ok(store) => store,
+        Err(e) => {
+            println!("[WARN] Could not open the root certificate store: {}", e);
+            return roots;
+        }
+    };
+    let mut context = unsafe { CertEnumCertificatesInStore(store, None) };
+    while !context.is_null() {
+        let encoded = unsafe {
+            std::slice::from_raw_parts((*context).pbCertEncoded, (*context).cbCertEncoded as usize)
+        };
+        // Roots with keys or encodings this module does not read are of no
+        // use for completing a chain anyway.
+        if let Ok(root) = Certificate::from_der(encoded) {
+            roots.push(root);
+        }
+        context = unsafe { CertEnumCertificatesInStore(store, Some(context as *const _)) };
+    }
+    let _ = unsafe { CertCloseStore(store, 0) };
+    roots
+}
+
+#[cfg(not(windows))]
+pub fn system_roots() -> Vec<Certificate> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::fixtures;
+
+    #[test]
+    fn reads_names_validity_and_key_of_a_certificate() {
+        let leaf = fixtures::leaf();
+        assert_eq!(leaf.subject.common_name(), Some("SignPath Foundation"));
+        assert_eq!(
+            leaf.subject.to_string(),
+            "CN=SignPath Foundation, O=SignPath Foundation"
+        );
+        assert_eq!(leaf.issuer.common_name(), Some("Fixture Code Signing CA"));
+        assert_eq!(leaf.not_before, 1_704_067_200);
+        assert_eq!(leaf.not_after, 1_767_225_600);
+        assert_eq!(leaf.spki_sha256(), fixtures::LEAF_SPKI_SHA256);
+        assert_eq!(leaf.spki_sha256(), fixtures::renewed_leaf().spki_sha256());
+        assert_ne!(leaf.thumbprint(), fixtures::renewed_leaf().thumbprint());
+    }
+
+    #[test]
+    fn reads_basic_constraints_and_extended_key_usage() {
+        let leaf = fixtures::leaf();
+        assert!(!leaf.is_ca);
+        assert!(leaf.has_extended_key_usage(CODE_SIGNING));
+        assert!(!leaf.has_extended_key_usage(TIME_STAMPING));
+        assert!(fixtures::intermediate().is_ca);
+        assert!(fixtures::root().is_ca);
+        assert!(!fixtures::intermediate().has_extended_key_usage(CODE_SIGNING));
+    }
+
+    #[test]
+    fn checks_which_key_signed_a_certificate() {
+        let leaf = fixtures::leaf();
+        assert!(leaf.is_signed_by(&fixtures::intermediate()).unwrap());
+        assert!(!leaf.is_signed_by(&fixtures::root()).unwrap());
+        assert!(fixtures::intermediate()
+            .is_signed_by(&fixtures::root())
+            .unwrap());
+        assert!(!fixtures::impostor()
+            .is_signed_by(&fixtures::intermediate())
+            .unwrap());
+    }
+
+    #[test]
+    fn completes_the_chain_with_the_root_that_signed_it() {
+        let roots = [fixtures::other_root(), fixtures::root()];
+        let chain = complete_chain(vec![fixtures::leaf(), fixtures::intermediate()], &roots);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain[2].thumbprint(), fixtures::root().thumbprint());
+
+        let impostor = complete_chain(vec![fixtures::impostor()], &[fixtures::root()]);
+        assert_eq!(impostor.len(), 1);
+        assert_eq!(complete_chain(chain.clone(), &roots).len(), 3);
+    }
+
+    #[test]
+    fn builds_the_chain_from_the_leaf_upwards() {
+        let pool = [
+            fixtures::other_root(),
+            fixtures::root(),
+            fixtures::intermediate(),
+        ];
+        let chain = build_chain(fixtures::leaf(), &pool);
+        let names: Vec<_> = chain
+            .iter()
+            .map(|cert| cert.subject.common_name().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "SignPath Foundation",
+                "Fixture Code Signing CA",
+                "Fixture Root CA"
+            ]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Just enough DER to walk certificates and signatures: definite-length
+//! TLVs, object identifiers, strings and times.
+
+use crate::date::{days_from_civil, UtcTime};
+use std::fmt;
+
+pub const BOOLEAN: u8 = 0x01;
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const OID: u8 = 0x06;
+pub const UTF8_STRING: u8 = 0x0c;
+pub const PRINTABLE_STRING: u8 = 0x13;
+pub const IA5_STRING: u8 = 0x16;
+pub const UTC_TIME: u8 = 0x17;
+pub const GENERALIZED_TIME: u8 = 0x18;
+pub const BMP_STRING: u8 = 0x1e;
+pub const SEQUENCE: u8 = 0x30;
+pub const SET: u8 = 0x31;
+
+/// Tag of the `[n]` context-specific constructed field.
+pub const fn context(n: u8) -> u8 {
+    0xa0 | n
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DerError(pub String);
+
+impl fmt::Display for DerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed DER: {}", self.0)
+    }
+}
+
+fn malformed(reason: impl Into<String>) -> DerError {
+    DerError(reason.into())
+}
+
+/// One tag-length-value element.
+#[derive(Clone, Copy, Debug)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub value: &'a [u8],
+    /// The whole element, header included.
+    pub raw: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// A reader over the elements inside a constructed value.
+    pub fn contents(&self) -> Reader<'a> {
+        Reader::new(self.value)
+    }
+}
+
+/// Reads consecutive elements from a buffer.
+#[derive(Clone, Debug)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    pub fn read(&mut self) -> Result<Tlv<'a>, DerError> {
+        let data = self.data;
+        let (&tag, rest) = data
+            .split_first()
+            .ok_or_else(|| malformed("unexpected end"))?;
+        if tag & 0x1f == 0x1f {
+            return Err(malformed("multi-byte tags are not supported"));
+        }
+        let (&first, rest) = rest
+            .split_first()
+            .ok_or_else(|| malformed("missing length"))?;
+
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(malformed("unsupported length"));
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+            (len, &rest[count..])
+        };
+        if rest.len() < len {
+            return Err(malformed("element longer than its container"));
+        }
+
+        let header_len = data.len() - rest.len();
+        self.data = &rest[len..];
+        Ok(Tlv {
+            tag,
+            value: &rest[..len],
+            raw: &data[..header_len + len],
+        })
+    }
+
+    /// Reads the next element, which must have `tag`.
+    pub fn expect(&mut self, tag: u8) -> Result<Tlv<'a>, DerError> {
+        let tlv = self.read()?;
+        if tlv.tag != tag {
+            return Err(malformed(format!(
+                "expected tag {:#04x}, found {:#04x}",
+                tag, tlv.tag
+            )));
+        }
+        Ok(tlv)
+    }
+
+    /// Reads the next element if it has `tag`.
+    pub fn optional(&mut self, tag: u8) -> Result<Option<Tlv<'a>>, DerError> {
+        if self.peek_tag() == Some(tag) {
+            self.read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Formats an object identifier in dotted notation.
+pub fn oid_string(value: &[u8]) -> Result<String, DerError> {
+    let mut arcs = Vec::new();
+    let mut arc = 0u64;
+    for (i, &byte) in value.iter().enumerate() {
+        if arc > u64::MAX >> 7 {
+            return Err(malformed("object identifier arc too large"));
+        }
+        arc = (arc << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        } else if i == value.len() - 1 {
+            return Err(malformed("truncated object identifier"));
+        }
+    }
+    if arcs.is_empty() {
+        return Err(malformed("empty object identifier"));
+    }
+
+    Ok(arcs
+        .iter()
+        .map(|arc| arc.to_string())
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+/// Decodes the string types found in distinguished names.
+pub fn string(tlv: &Tlv) -> Result<String, DerError> {
+    match tlv.tag {
+        UTF8_STRING | PRINTABLE_STRING | IA5_STRING => {
+            String::from_utf8(tlv.value.to_vec()).map_err(|_| malformed("invalid string encoding"))
+        }
+        BMP_STRING => {
+            if tlv.value.len() % 2 == 1 {
+                return Err(malformed("odd BMPString length"));
+            }
+            let units: Vec<u16> = tlv
+                .value
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16(&units).map_err(|_| malformed("invalid BMPString"))
+        }
+        // T61String and the like: Latin-1 is as close as it gets.
+        _ => Ok(tlv.value.iter().map(|&byte| byte as char).collect()),
+    }
+}
+
+/// Decodes a `UTCTime` or `GeneralizedTime` in UTC to Unix seconds.
+pub fn time(tlv: &Tlv) -> Result<i64, DerError> {
+    let text = std::str::from_utf8(tlv.value).map_err(|_| malformed("invalid time"))?;
+    let digits = text
+        .strip_suffix('Z')
+        .ok_or_else(|| malformed("time must be in UTC"))?;
+    let digits = digits.split('.').next().unwrap_or_default();
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(malformed("invalid time"));
+    }
+    let field = |range: std::ops::Range<usize>| -> Result<i64, DerError> {
+        digits
+            .get(range)
+            .and_then(|field| field.parse().ok())
+            .ok_or_else(|| malformed("invalid time"))
+    };
+
+    let (year, rest) = match tlv.tag {
+        UTC_TIME if digits.len() == 12 => {
+            let year = field(0..2)?;
+            (if year < 50 { 2000 + year } else { 1900 + year }, 2)
+        }
+        GENERALIZED_TIME if digits.len() == 14 => (field(0..4)?, 4),
+        _ => return Err(malformed("invalid time")),
+    };
+    let month = field(rest..rest + 2)?;
+    let day = field(rest + 2..rest + 4)?;
+    let hour = field(rest + 4..rest + 6)?;
+    let minute = field(rest + 6..rest + 8)?;
+    let second = field(rest + 8..rest + 10)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return Err(malformed("invalid time"));
+    }
+
+    Ok(days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Formats Unix seconds as `YYYY-MM-DD HH:MM:SS UTC`.
+pub fn format_time(unix: i64) -> String {
+    let time = UtcTime::from_unix(unix);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_elements_and_values() {
+        // SEQUENCE { OID 2.5.4.3, UTCTime 240101000000Z, PrintableString "ab" }
+        let mut data = vec![0x30, 0x18];
+        data.extend([0x06, 0x03, 0x55, 0x04, 0x03]);
+        data.extend([0x17, 0x0d]);
+        data.extend(b"240101000000Z");
+        data.extend([0x13, 0x02, b'a', b'b']);
+
+        let sequence = Reader::new(&data).expect(SEQUENCE).unwrap();
+        let mut fields = sequence.contents();
+        assert_eq!(
+            oid_string(fields.expect(OID).unwrap().value).unwrap(),
+            "2.5.4.3"
+        );
+        assert_eq!(time(&fields.read().unwrap()).unwrap(), 1_704_067_200);
+        assert_eq!(string(&fields.read().unwrap()).unwrap(), "ab");
+        assert!(fields.is_empty());
+
+        assert!(Reader::new(&data[..10]).read().is_err());
+        assert_eq!(format_time(1_704_067_199), "2023-12-31 23:59:59 UTC");
+    }
+}
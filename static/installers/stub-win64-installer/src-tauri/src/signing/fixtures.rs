@@ -0,0 +1,99 @@
+//! Certificates made by `fixtures/generate.py`: a root, an intermediate and
+//! a "SignPath Foundation" leaf (also renewed with the same key), plus a
+//! "Not SignPath Foundation Ltd" leaf from an unrelated root and a
+//! "Microsoft Corporation" leaf from a counterfeit one, and an
+//! "Open Source Developer, Ryosuke Asano" certificate the leaf issued as if
+//! it were a CA. The PE images
+//! come from `fixtures/sign.py`; the timestamp authority of all but one is
+//! issued by the root.
+
+use super::cert::Certificate;
+
+pub const LEAF_SPKI_SHA256: &str =
+    "5f070c858afca41bf6ffad4bc07b342a34b651e3a7cc6c3d5583d0966e445c51";
+pub const INTERMEDIATE_SPKI_SHA256: &str =
+    "5f6e3345080cec6f4a1ea3fee7b3856c93818344cb46294b0c54c82ac0b7e843";
+
+/// Where the fixture images keep their checksum and their code.
+pub const IMAGE_CHECKSUM_OFFSET: usize = 0x98;
+pub const IMAGE_CODE_OFFSET: usize = 0x200;
+
+fn load(der: &[u8]) -> Certificate {
+    Certificate::from_der(der).unwrap()
+}
+
+pub fn root() -> Certificate {
+    load(include_bytes!("fixtures/root.der"))
+}
+
+pub fn intermediate() -> Certificate {
+    load(include_bytes!("fixtures/intermediate.der"))
+}
+
+pub fn leaf() -> Certificate {
+    load(include_bytes!("fixtures/leaf.der"))
+}
+
+pub fn renewed_leaf() -> Certificate {
+    load(include_bytes!("fixtures/leaf_renewed.der"))
+}
+
+pub fn other_root() -> Certificate {
+    load(include_bytes!("fixtures/other_root.der"))
+}
+
+pub fn impostor() -> Certificate {
+    load(include_bytes!("fixtures/impostor.der"))
+}
+
+pub fn counterfeit_root() -> Certificate {
+    load(include_bytes!("fixtures/counterfeit_root.der"))
+}
+
+pub fn counterfeit_microsoft() -> Certificate {
+    load(include_bytes!("fixtures/counterfeit_microsoft.der"))
+}
+
+/// A minimal PE32+ image signed by [`leaf`], including [`intermediate`],
+/// and timestamped 2025-06-01 12:00 UTC.
+pub fn signed_image() -> Vec<u8> {
+    include_bytes!("fixtures/signed.exe").to_vec()
+}
+
+/// Signed like [`signed_image`], but timestamped 2026-03-01, after the
+/// leaf expired.
+pub fn late_timestamp_image() -> Vec<u8> {
+    include_bytes!("fixtures/late_timestamp.exe").to_vec()
+}
+
+/// Signed like [`signed_image`], but timestamped by an authority that
+/// names the fixture root as its issuer without being signed by it.
+pub fn rogue_timestamp_image() -> Vec<u8> {
+    include_bytes!("fixtures/rogue_timestamp.exe").to_vec()
+}
+
+/// Signed like [`signed_image`], but timestamped by [`leaf`], which is not
+/// a timestamp authority.
+pub fn leaf_timestamp_image() -> Vec<u8> {
+    include_bytes!("fixtures/leaf_timestamp.exe").to_vec()
+}
+
+/// Signed by the certificate [`leaf`] issued, including it and
+/// [`intermediate`], and timestamped like [`signed_image`].
+pub fn minted_image() -> Vec<u8> {
+    include_bytes!("fixtures/minted.exe").to_vec()
+}
+
+/// Signed by the timestamp authority, whose certificate is not for code
+/// signing.
+pub fn tsa_signed_image() -> Vec<u8> {
+    include_bytes!("fixtures/tsa_signed.exe").to_vec()
+}
+
+pub fn untimestamped_image() -> Vec<u8> {
+    include_bytes!("fixtures/untimestamped.exe").to_vec()
+}
+
+pub fn unsigned_image() -> Vec<u8> {
+    include_bytes!("fixtures/unsigned.exe").to_vec()
+}
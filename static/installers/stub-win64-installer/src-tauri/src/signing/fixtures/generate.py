@@ -0,0 +1,136 @@
+"""Regenerates the certificates the signing tests use.
+
+Run with `python3 generate.py` in this directory; needs `cryptography`.
+Keys are generated anew, so every pin in the tests changes with them; run
+`sign.py` afterwards to sign the test images with the new leaf.
+"""
+
+import datetime
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import rsa
+from cryptography.x509.oid import ExtendedKeyUsageOID, NameOID
+
+
+def name(common_name, organization=None):
+    attributes = [x509.NameAttribute(NameOID.COMMON_NAME, common_name)]
+    if organization:
+        attributes.append(x509.NameAttribute(NameOID.ORGANIZATION_NAME, organization))
+    return x509.Name(attributes)
+
+
+def key():
+    return rsa.generate_private_key(public_exponent=65537, key_size=2048)
+
+
+def issue(subject, subject_key, issuer, issuer_key, not_before, not_after, ca, usage=None):
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(subject)
+        .issuer_name(issuer)
+        .public_key(subject_key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(not_before)
+        .not_valid_after(not_after)
+        .add_extension(x509.BasicConstraints(ca=ca, path_length=None), critical=True)
+    )
+    if not ca:
+        builder = builder.add_extension(
+            x509.ExtendedKeyUsage([usage or ExtendedKeyUsageOID.CODE_SIGNING]),
+            critical=usage is not None,
+        )
+    return builder.sign(issuer_key, hashes.SHA256())
+
+
+def save(cert, file_name):
+    with open(file_name, "wb") as out:
+        out.write(cert.public_bytes(serialization.Encoding.DER))
+
+
+def save_key(private_key, file_name):
+    with open(file_name, "wb") as out:
+        out.write(
+            private_key.private_bytes(
+                serialization.Encoding.PEM,
+                serialization.PrivateFormat.PKCS8,
+                serialization.NoEncryption(),
+            )
+        )
+
+
+def utc(year, month, day):
+    return datetime.datetime(year, month, day, tzinfo=datetime.timezone.utc)
+
+
+root_key, ca_key, leaf_key = key(), key(), key()
+root_name = name("Fixture Root CA", "Fixture Trust")
+ca_name = name("Fixture Code Signing CA", "Fixture Trust")
+leaf_name = name("SignPath Foundation", "SignPath Foundation")
+
+root = issue(root_name, root_key, root_name, root_key, utc(2020, 1, 1), utc(2040, 1, 1), True)
+ca = issue(ca_name, ca_key, root_name, root_key, utc(2020, 1, 1), utc(2035, 1, 1), True)
+leaf = issue(leaf_name, leaf_key, ca_name, ca_key, utc(2024, 1, 1), utc(2026, 1, 1), False)
+# Renewed with the same key, as code signing certificates often are.
+renewed = issue(leaf_name, leaf_key, ca_name, ca_key, utc(2025, 12, 1), utc(2028, 1, 1), False)
+
+tsa_key = key()
+tsa = issue(
+    name("Fixture Timestamp Authority", "Fixture Trust"), tsa_key, root_name, root_key,
+    utc(2020, 1, 1), utc(2035, 1, 1), False, ExtendedKeyUsageOID.TIME_STAMPING,
+)
+
+# Claims to be issued by the fixture root, but is signed with another key.
+rogue_tsa_key, rogue_root_key = key(), key()
+rogue_tsa = issue(
+    name("Rogue Timestamp Authority", "Fixture Trust"), rogue_tsa_key, root_name, rogue_root_key,
+    utc(2020, 1, 1), utc(2035, 1, 1), False, ExtendedKeyUsageOID.TIME_STAMPING,
+)
+
+other_root_key, impostor_key = key(), key()
+other_root_name = name("Other Root CA")
+other_root = issue(
+    other_root_name, other_root_key, other_root_name, other_root_key,
+    utc(2020, 1, 1), utc(2040, 1, 1), True,
+)
+impostor = issue(
+    name("Not SignPath Foundation Ltd"), impostor_key, other_root_name, other_root_key,
+    utc(2024, 1, 1), utc(2026, 1, 1), False,
+)
+
+# An end-entity certificate used as a CA: the leaf issues one for another
+# name Floorp releases are signed under.
+minted_key = key()
+minted = issue(
+    name("Open Source Developer, Ryosuke Asano"), minted_key, leaf_name, leaf_key,
+    utc(2024, 1, 1), utc(2026, 1, 1), False,
+)
+
+# Carries the name WebView2 bootstrappers are signed under, from a root
+# that is not Microsoft's.
+counterfeit_root_key, counterfeit_key = key(), key()
+counterfeit_root_name = name("Counterfeit Root CA")
+counterfeit_root = issue(
+    counterfeit_root_name, counterfeit_root_key, counterfeit_root_name, counterfeit_root_key,
+    utc(2020, 1, 1), utc(2040, 1, 1), True,
+)
+counterfeit_microsoft = issue(
+    name("Microsoft Corporation", "Microsoft Corporation"), counterfeit_key,
+    counterfeit_root_name, counterfeit_root_key, utc(2024, 1, 1), utc(2026, 1, 1), False,
+)
+
+save(root, "root.der")
+save(ca, "intermediate.der")
+save(leaf, "leaf.der")
+save(renewed, "leaf_renewed.der")
+save(other_root, "other_root.der")
+save(impostor, "impostor.der")
+save(counterfeit_root, "counterfeit_root.der")
+save(counterfeit_microsoft, "counterfeit_microsoft.der")
+save(minted, "minted.der")
+save(tsa, "tsa.der")
+save(rogue_tsa, "rogue_tsa.der")
+save_key(leaf_key, "leaf.key")
+save_key(tsa_key, "tsa.key")
+save_key(rogue_tsa_key, "rogue_tsa.key")
+save_key(minted_key, "minted.key")
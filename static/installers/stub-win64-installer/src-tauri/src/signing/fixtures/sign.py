@@ -0,0 +1,234 @@
+"""Builds the PE images the Authenticode tests use.
+
+Run with `python3 sign.py` in this directory after `generate.py`; needs
+`cryptography`. Writes `unsigned.exe`, a minimal PE32+ image, and signed
+copies of it, signed with `leaf.key` the way signtool does and including
+the leaf and intermediate certificates:
+
+- `signed.exe`, with an RFC 3161 timestamp from `tsa.key` of 2025-06-01,
+  inside the validity of the leaf;
+- `late_timestamp.exe`, timestamped 2026-03-01, after the leaf expired;
+- `rogue_timestamp.exe`, timestamped 2025-06-01 by `rogue_tsa.key`, whose
+  certificate names the fixture root as its issuer without being signed by it;
+- `untimestamped.exe`, without a timestamp;
+- `leaf_timestamp.exe`, timestamped 2025-06-01 by `leaf.key`, whose
+  certificate is not for time stamping;
+- `minted.exe`, signed with `minted.key` instead, whose certificate the
+  leaf issued as if it were a CA, including the leaf and intermediate;
+- `tsa_signed.exe`, signed with `tsa.key`, whose certificate is not for
+  code signing.
+"""
+
+import datetime
+import hashlib
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import padding
+
+PE_OFFSET = 0x40
+OPTIONAL_HEADER = PE_OFFSET + 24
+CHECKSUM = OPTIONAL_HEADER + 64
+SECURITY_ENTRY = OPTIONAL_HEADER + 112 + 4 * 8
+HEADERS_SIZE = 0x200
+CODE_SIZE = 0x200
+
+SHA256 = "2.16.840.1.101.3.4.2.1"
+RSA_ENCRYPTION = "1.2.840.113549.1.1.1"
+SIGNED_DATA = "1.2.840.113549.1.7.2"
+CONTENT_TYPE = "1.2.840.113549.1.9.3"
+MESSAGE_DIGEST = "1.2.840.113549.1.9.4"
+SPC_INDIRECT_DATA = "1.3.6.1.4.1.311.2.1.4"
+SPC_SP_OPUS_INFO = "1.3.6.1.4.1.311.2.1.12"
+SPC_PE_IMAGE_DATA = "1.3.6.1.4.1.311.2.1.15"
+TST_INFO = "1.2.840.113549.1.9.16.1.4"
+RFC3161_COUNTERSIGNATURE = "1.3.6.1.4.1.311.3.3.1"
+TIMESTAMP_POLICY = "1.2.3.4.1"
+
+
+def tlv(tag, *contents):
+    body = b"".join(contents)
+    if len(body) < 0x80:
+        length = bytes([len(body)])
+    else:
+        size = (len(body).bit_length() + 7) // 8
+        length = bytes([0x80 | size]) + len(body).to_bytes(size, "big")
+    return bytes([tag]) + length + body
+
+
+def seq(*contents):
+    return tlv(0x30, *contents)
+
+
+def set_of(*contents):
+    return tlv(0x31, *sorted(contents))
+
+
+def oid(dotted):
+    arcs = [int(arc) for arc in dotted.split(".")]
+    body = bytes([arcs[0] * 40 + arcs[1]])
+    for arc in arcs[2:]:
+        chunk = [arc & 0x7F]
+        arc >>= 7
+        while arc:
+            chunk.insert(0, 0x80 | (arc & 0x7F))
+            arc >>= 7
+        body += bytes(chunk)
+    return tlv(0x06, body)
+
+
+def integer(value):
+    return tlv(0x02, value.to_bytes(value.bit_length() // 8 + 1, "big"))
+
+
+def octets(value):
+    return tlv(0x04, value)
+
+
+def algorithm(dotted):
+    return seq(oid(dotted), tlv(0x05))
+
+
+def image():
+    dos = bytearray(PE_OFFSET)
+    dos[0:2] = b"MZ"
+    struct.pack_into("<I", dos, 0x3C, PE_OFFSET)
+
+    # AMD64, one section, 240 bytes of optional header, executable.
+    coff = b"PE\0\0" + struct.pack("<HHIIIHH", 0x8664, 1, 0, 0, 0, 240, 0x22)
+
+    optional = bytearray(240)
+    struct.pack_into("<HBBIIIII", optional, 0, 0x20B, 14, 0, CODE_SIZE, 0, 0, 0x1000, 0x1000)
+    struct.pack_into("<QII", optional, 24, 0x140000000, 0x1000, 0x200)
+    struct.pack_into("<HHHHHH", optional, 40, 6, 0, 0, 0, 6, 0)
+    struct.pack_into("<IIIIHH", optional, 52, 0, 0x2000, HEADERS_SIZE, 0, 3, 0x8160)
+    struct.pack_into("<QQQQII", optional, 72, 0x100000, 0x1000, 0x100000, 0x1000, 0, 16)
+
+    section = struct.pack(
+        "<8sIIIIIIHHI", b".text", 3, 0x1000, CODE_SIZE, HEADERS_SIZE, 0, 0, 0, 0, 0x60000020
+    )
+
+    headers = bytes(dos) + coff + bytes(optional) + section
+    code = b"\x31\xc0\xc3"  # xor eax, eax; ret
+    return bytearray(headers.ljust(HEADERS_SIZE, b"\0") + code.ljust(CODE_SIZE, b"\0"))
+
+
+def authenticode_digest(data):
+    return hashlib.sha256(
+        data[:CHECKSUM] + data[CHECKSUM + 4 : SECURITY_ENTRY] + data[SECURITY_ENTRY + 8 :]
+    ).digest()
+
+
+def signed_data(content_type, content, signer, key):
+    """The signature value and SignerInfo fields for `content`, the DER that
+    goes inside the encapsulated content's `[0]`."""
+    content_value = content[2:] if content[1] < 0x80 else content[2 + (content[1] & 0x7F) :]
+    attributes = [
+        seq(oid(CONTENT_TYPE), set_of(oid(content_type))),
+        seq(oid(MESSAGE_DIGEST), set_of(octets(hashlib.sha256(content_value).digest()))),
+    ]
+    if content_type == SPC_INDIRECT_DATA:
+        attributes.append(seq(oid(SPC_SP_OPUS_INFO), set_of(seq())))
+    signed = key.sign(set_of(*attributes), padding.PKCS1v15(), hashes.SHA256())
+
+    signer_info = [
+        integer(1),
+        seq(signer.issuer.public_bytes(), integer(signer.serial_number)),
+        algorithm(SHA256),
+        tlv(0xA0, *sorted(attributes)),
+        algorithm(RSA_ENCRYPTION),
+        octets(signed),
+    ]
+    return signed, signer_info
+
+
+def pkcs7(content_type, content, certificates, signer_info):
+    return seq(
+        oid(SIGNED_DATA),
+        tlv(
+            0xA0,
+            seq(
+                integer(1),
+                set_of(algorithm(SHA256)),
+                seq(oid(content_type), tlv(0xA0, content)),
+                tlv(0xA0, *(cert.public_bytes(serialization.Encoding.DER) for cert in certificates)),
+                set_of(seq(*signer_info)),
+            ),
+        ),
+    )
+
+
+def timestamp(signed, time, tsa, tsa_key, tsa_issuers):
+    """An RFC 3161 timestamp token over the signature value `signed`."""
+    tst_info = seq(
+        integer(1),
+        oid(TIMESTAMP_POLICY),
+        seq(algorithm(SHA256), octets(hashlib.sha256(signed).digest())),
+        integer(int(time.timestamp())),
+        tlv(0x18, time.strftime("%Y%m%d%H%M%SZ").encode()),
+    )
+    content = octets(tst_info)
+    _, signer_info = signed_data(TST_INFO, content, tsa, tsa_key)
+    return pkcs7(TST_INFO, content, [tsa, *tsa_issuers], signer_info)
+
+
+def signature(data, certificates, key, tsa=None, tsa_key=None, tsa_issuers=(), time=None):
+    obsolete = tlv(0xA0, tlv(0xA2, tlv(0x80, "<<<Obsolete>>>".encode("utf-16-be"))))
+    pe_image_data = seq(tlv(0x03, b"\0"), obsolete)
+    indirect = seq(
+        seq(oid(SPC_PE_IMAGE_DATA), pe_image_data),
+        seq(algorithm(SHA256), octets(authenticode_digest(data))),
+    )
+    signed, signer_info = signed_data(SPC_INDIRECT_DATA, indirect, certificates[0], key)
+    if time is not None:
+        token = timestamp(signed, time, tsa, tsa_key, tsa_issuers)
+        signer_info.append(tlv(0xA1, seq(oid(RFC3161_COUNTERSIGNATURE), set_of(token))))
+    return pkcs7(SPC_INDIRECT_DATA, indirect, certificates, signer_info)
+
+
+def load(file_name):
+    with open(file_name, "rb") as source:
+        return x509.load_der_x509_certificate(source.read())
+
+
+def load_key(file_name):
+    with open(file_name, "rb") as source:
+        return serialization.load_pem_private_key(source.read(), password=None)
+
+
+def signed_image(file_name, signer="leaf", issuers=("intermediate",), **timestamp):
+    certificates = [load(f"{signer}.der")] + [load(f"{issuer}.der") for issuer in issuers]
+    blob = signature(unsigned, certificates, load_key(f"{signer}.key"), **timestamp)
+    blob += b"\0" * (-len(blob) % 8)
+    # WIN_CERTIFICATE: length, revision 2.0, PKCS #7 signed data.
+    table = struct.pack("<IHH", 8 + len(blob), 0x0200, 0x0002) + blob
+
+    signed = bytearray(unsigned)
+    struct.pack_into("<II", signed, SECURITY_ENTRY, len(unsigned), len(table))
+    with open(file_name, "wb") as out:
+        out.write(signed + table)
+
+
+def utc(year, month, day, hour=0):
+    return datetime.datetime(year, month, day, hour, tzinfo=datetime.timezone.utc)
+
+
+unsigned = image()
+with open("unsigned.exe", "wb") as out:
+    out.write(unsigned)
+
+tsa = {"tsa": load("tsa.der"), "tsa_key": load_key("tsa.key")}
+signed_image("signed.exe", time=utc(2025, 6, 1, 12), **tsa)
+signed_image("late_timestamp.exe", time=utc(2026, 3, 1), **tsa)
+rogue = {"tsa": load("rogue_tsa.der"), "tsa_key": load_key("rogue_tsa.key")}
+signed_image("rogue_timestamp.exe", time=utc(2025, 6, 1, 12), **rogue)
+signed_image("untimestamped.exe")
+leaf = {"tsa": load("leaf.der"), "tsa_key": load_key("leaf.key")}
+signed_image(
+    "leaf_timestamp.exe", time=utc(2025, 6, 1, 12), tsa_issuers=[load("intermediate.der")], **leaf
+)
+signed_image(
+    "minted.exe", signer="minted", issuers=("leaf", "intermediate"), time=utc(2025, 6, 1, 12), **tsa
+)
+signed_image("tsa_signed.exe", signer="tsa", issuers=(), time=utc(2025, 6, 1, 12), **tsa)
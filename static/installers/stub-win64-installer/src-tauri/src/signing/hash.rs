@@ -0,0 +1,78 @@
+//! The digest algorithms signatures are accepted with.
+
+use sha2::digest::DynDigest;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// The algorithm of a digest algorithm identifier. SHA-1 and MD5 are
+    /// not accepted.
+    pub fn from_oid(oid: &str) -> Option<Self> {
+        match oid {
+            "2.16.840.1.101.3.4.2.1" => Some(HashAlgorithm::Sha256),
+            "2.16.840.1.101.3.4.2.2" => Some(HashAlgorithm::Sha384),
+            "2.16.840.1.101.3.4.2.3" => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    /// The digest of an RSA PKCS #1 v1.5 signature algorithm identifier.
+    pub fn from_rsa_signature_oid(oid: &str) -> Option<Self> {
+        match oid {
+            "1.2.840.113549.1.1.11" => Some(HashAlgorithm::Sha256),
+            "1.2.840.113549.1.1.12" => Some(HashAlgorithm::Sha384),
+            "1.2.840.113549.1.1.13" => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn hasher(self) -> Box<dyn DynDigest> {
+        match self {
+            HashAlgorithm::Sha256 => Box::new(Sha256::new()),
+            HashAlgorithm::Sha384 => Box::new(Sha384::new()),
+            HashAlgorithm::Sha512 => Box::new(Sha512::new()),
+        }
+    }
+
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize().into_vec()
+    }
+
+    /// The DER of a `DigestInfo` up to the digest itself, as it appears in
+    /// a PKCS #1 v1.5 signature.
+    pub fn digest_info_prefix(self) -> &'static [u8] {
+        match self {
+            HashAlgorithm::Sha256 => &[
+                0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ],
+            HashAlgorithm::Sha384 => &[
+                0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x02, 0x05, 0x00, 0x04, 0x30,
+            ],
+            HashAlgorithm::Sha512 => &[
+                0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x03, 0x05, 0x00, 0x04, 0x40,
+            ],
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Sha384 => "SHA-384",
+            HashAlgorithm::Sha512 => "SHA-512",
+        })
+    }
+}
@@ -0,0 +1,31 @@
+//! Checking the signature of an installer and deciding whether its signer
+//! is trusted.
+//!
+//! [`verify_file`] checks the Authenticode signature and its chain on any
+//! platform, with Windows checking it again where available; the chain is
+//! then matched against the pins of a [`TrustPolicy`] to decide whether it
+//! belongs to a signer Floorp releases are made with.
+
+mod authenticode;
+mod cert;
+mod der;
+#[cfg(test)]
+mod fixtures;
+mod hash;
+mod pe;
+mod pkcs7;
+mod policy;
+mod report;
+mod rsa;
+mod timestamp;
+
+pub use authenticode::{verify, verify_file, Signature, SignatureError};
+pub use cert::{build_chain, complete_chain, system_roots, Certificate, Name};
+pub use der::{format_time, DerError};
+pub use hash::HashAlgorithm;
+pub use policy::{
+    evaluate, floorp_pins, microsoft_pins, PinMatch, PolicyError, RevocationCheck, SignerPin,
+    TrustPolicy,
+};
+pub use report::{win_verify_trust_status, Rule, RuleFailure, SignatureReport, SignatureReports};
+pub use timestamp::Timestamp;
@@ -0,0 +1,150 @@
+//! Where a PE image keeps its signature, and the digest Authenticode signs.
+
+use super::authenticode::SignatureError;
+use super::hash::HashAlgorithm;
+use std::io::{self, Read, Seek, SeekFrom};
+
+const PE32: u16 = 0x10b;
+const PE32_PLUS: u16 = 0x20b;
+/// Index of the certificate table among the data directories.
+const SECURITY_DIRECTORY: u32 = 4;
+const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+
+/// The offsets Authenticode cares about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Offset of the optional header's `CheckSum`, left out of the digest.
+    checksum: u64,
+    /// Offset of the certificate table's directory entry, also left out.
+    security_entry: u64,
+    /// The certificate table, which must end the file.
+    table_offset: u64,
+    table_len: u64,
+}
+
+impl Layout {
+    /// Reads the headers of the image in `reader`. Fails with
+    /// [`SignatureError::Unsigned`] when it has no certificate table.
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, SignatureError> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+
+        let dos = read_at(reader, 0, 0x40)?;
+        if &dos[..2] != b"MZ" {
+            return Err(malformed("not a PE image"));
+        }
+        let pe = u64::from(u32_at(&dos, 0x3c));
+        let headers = read_at(reader, pe, 24)?;
+        if &headers[..4] != b"PE\0\0" {
+            return Err(malformed("not a PE image"));
+        }
+        let optional = pe + 24;
+        let optional_len = u64::from(u16_at(&headers, 20));
+        let optional_header = read_at(reader, optional, optional_len as usize)?;
+        if optional_header.len() < 2 {
+            return Err(malformed("optional header missing"));
+        }
+
+        let (count_at, directories_at) = match u16_at(&optional_header, 0) {
+            PE32 => (92, 96),
+            PE32_PLUS => (108, 112),
+            _ => return Err(malformed("unknown optional header")),
+        };
+        if optional_header.len() < count_at + 4
+            || u32_at(&optional_header, count_at) <= SECURITY_DIRECTORY
+        {
+            return Err(SignatureError::Unsigned);
+        }
+        let entry_at = directories_at + SECURITY_DIRECTORY as usize * 8;
+        if optional_header.len() < entry_at + 8 {
+            return Err(malformed("data directories cut short"));
+        }
+        let table_offset = u64::from(u32_at(&optional_header, entry_at));
+        let table_len = u64::from(u32_at(&optional_header, entry_at + 4));
+        if table_offset == 0 || table_len == 0 {
+            return Err(SignatureError::Unsigned);
+        }
+        let security_entry = optional + entry_at as u64;
+        if table_offset < security_entry + 8 || table_offset + table_len != file_len {
+            return Err(malformed("certificate table is not at the end of the file"));
+        }
+
+        Ok(Layout {
+            checksum: optional + 64,
+            security_entry,
+            table_offset,
+            table_len,
+        })
+    }
+
+    /// The first PKCS #7 signature in the certificate table.
+    pub fn signature<R: Read + Seek>(&self, reader: &mut R) -> Result<Vec<u8>, SignatureError> {
+        let table = read_at(reader, self.table_offset, self.table_len as usize)?;
+        if table.len() < 8 {
+            return Err(malformed("certificate table too short"));
+        }
+        let len = u32_at(&table, 0) as usize;
+        if len < 8 || len > table.len() {
+            return Err(malformed("certificate entry does not fit its table"));
+        }
+        if u16_at(&table, 4) != WIN_CERT_REVISION_2_0
+            || u16_at(&table, 6) != WIN_CERT_TYPE_PKCS_SIGNED_DATA
+        {
+            return Err(malformed("certificate entry is not a PKCS #7 signature"));
+        }
+        Ok(table[8..len].to_vec())
+    }
+
+    /// The Authenticode digest: the whole image up to its certificate table,
+    /// except for the checksum and the certificate table's directory entry.
+    pub fn digest<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        algorithm: HashAlgorithm,
+    ) -> Result<Vec<u8>, SignatureError> {
+        let mut hasher = algorithm.hasher();
+        let ranges = [
+            (0, self.checksum),
+            (self.checksum + 4, self.security_entry),
+            (self.security_entry + 8, self.table_offset),
+        ];
+        let mut buf = vec![0u8; 64 * 1024];
+        for (start, end) in ranges {
+            reader.seek(SeekFrom::Start(start))?;
+            let mut remaining = end - start;
+            while remaining > 0 {
+                let chunk = remaining.min(buf.len() as u64) as usize;
+                reader.read_exact(&mut buf[..chunk])?;
+                hasher.update(&buf[..chunk]);
+                remaining -= chunk as u64;
+            }
+        }
+        Ok(hasher.finalize().into_vec())
+    }
+}
+
+fn malformed(reason: &str) -> SignatureError {
+    SignatureError::Malformed(reason.to_string())
+}
+
+fn read_at<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    len: usize,
+) -> Result<Vec<u8>, SignatureError> {
+    let mut buf = vec![0u8; len];
+    reader.seek(SeekFrom::Start(offset))?;
+    match reader.read_exact(&mut buf) {
+        Ok(()) => Ok(buf),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Err(malformed("truncated image")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
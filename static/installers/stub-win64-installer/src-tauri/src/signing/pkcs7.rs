@@ -0,0 +1,230 @@
+//! PKCS #7 `SignedData`, as used by Authenticode signatures and by the
+//! timestamps countersigning them.
+
+use super::authenticode::SignatureError;
+use super::cert::{build_chain, Certificate, Name};
+use super::der::{self, DerError, Reader, Tlv};
+use super::hash::HashAlgorithm;
+use super::rsa::RSA_ENCRYPTION;
+
+const SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+const CONTENT_TYPE: &str = "1.2.840.113549.1.9.3";
+const MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+
+/// Signed content, the certificates that came with it, and who signed it.
+#[derive(Clone, Debug)]
+pub struct SignedData {
+    pub content_type: String,
+    /// The value of the element inside the content's `[0]`, without its
+    /// header, which is what the `messageDigest` attribute is a digest of.
+    pub content: Vec<u8>,
+    pub certificates: Vec<Certificate>,
+    pub signer: SignerInfo,
+}
+
+#[derive(Clone, Debug)]
+pub struct SignerInfo {
+    pub issuer: Name,
+    pub serial: Vec<u8>,
+    pub digest_algorithm: HashAlgorithm,
+    /// The authenticated attributes as a DER `SET`, which is what the
+    /// signature is over.
+    pub signed_attributes: Vec<u8>,
+    pub attributes: Vec<Attribute>,
+    pub signature: Vec<u8>,
+    /// Attributes added after signing, such as timestamps.
+    pub unsigned_attributes: Vec<Attribute>,
+}
+
+/// An attribute type and the DER of its values.
+#[derive(Clone, Debug)]
+pub struct Attribute {
+    pub oid: String,
+    pub values: Vec<Vec<u8>>,
+}
+
+impl SignedData {
+    pub fn parse(data: &[u8]) -> Result<Self, SignatureError> {
+        let mut content_info = Reader::new(data).expect(der::SEQUENCE)?.contents();
+        if der::oid_string(content_info.expect(der::OID)?.value)? != SIGNED_DATA {
+            return Err(malformed("not a PKCS #7 signature"));
+        }
+        let mut explicit = content_info.expect(der::context(0))?.contents();
+        let mut signed_data = explicit.expect(der::SEQUENCE)?.contents();
+
+        signed_data.expect(der::INTEGER)?; // version
+        signed_data.expect(der::SET)?; // digest algorithms
+        let mut encapsulated = signed_data.expect(der::SEQUENCE)?.contents();
+        let content_type = der::oid_string(encapsulated.expect(der::OID)?.value)?;
+        let content = encapsulated
+            .expect(der::context(0))?
+            .contents()
+            .read()?
+            .value
+            .to_vec();
+
+        let mut certificates = Vec::new();
+        if let Some(set) = signed_data.optional(der::context(0))? {
+            let mut entries = set.contents();
+            while !entries.is_empty() {
+                // Attribute certificates and the like are of no use here.
+                let entry = entries.read()?;
+                if entry.tag == der::SEQUENCE {
+                    certificates.push(Certificate::from_der(entry.raw)?);
+                }
+            }
+        }
+        signed_data.optional(der::context(1))?; // CRLs
+
+        let mut signers = signed_data.expect(der::SET)?.contents();
+        let signer = SignerInfo::parse(&signers.expect(der::SEQUENCE)?)?;
+        if !signers.is_empty() {
+            return Err(malformed("more than one signer"));
+        }
+
+        Ok(SignedData {
+            content_type,
+            content,
+            certificates,
+            signer,
+        })
+    }
+
+    /// Checks that the authenticated attributes are about the content and
+    /// that the key of the signer's certificate signed them. Returns that
+    /// certificate.
+    pub fn verify_signer(&self) -> Result<&Certificate, SignatureError> {
+        let signer = &self.signer;
+        let certificate = self
+            .certificates
+            .iter()
+            .find(|cert| cert.issuer == signer.issuer && cert.serial == signer.serial)
+            .ok_or(SignatureError::SignerNotFound)?;
+
+        let content_type = signer.attribute_value(CONTENT_TYPE, der::OID)?;
+        if der::oid_string(&content_type)? != self.content_type {
+            return Err(malformed("signed content type does not match"));
+        }
+        let content_digest = signer.digest_algorithm.digest(&self.content);
+        if signer.attribute_value(MESSAGE_DIGEST, der::OCTET_STRING)? != content_digest {
+            return Err(SignatureError::DigestMismatch);
+        }
+
+        let digest = signer.digest_algorithm.digest(&signer.signed_attributes);
+        if !certificate
+            .public_key()?
+            .verify(signer.digest_algorithm, &digest, &signer.signature)
+        {
+            return Err(SignatureError::BadSignature);
+        }
+        Ok(certificate)
+    }
+
+    /// Orders the certificates into the chain above `leaf`, checking that
+    /// each is signed by the next, which has to be a CA.
+    pub fn chain(&self, leaf: &Certificate) -> Result<Vec<Certificate>, SignatureError> {
+        let chain = build_chain(leaf.clone(), &self.certificates);
+        for pair in chain.windows(2) {
+            if !pair[1].is_ca {
+                return Err(SignatureError::IssuerNotCa {
+                    subject: pair[0].subject.to_string(),
+                });
+            }
+            if !pair[0].is_signed_by(&pair[1])? {
+                return Err(SignatureError::BrokenChain {
+                    subject: pair[0].subject.to_string(),
+                });
+            }
+        }
+        Ok(chain)
+    }
+}
+
+impl SignerInfo {
+    fn parse(tlv: &Tlv) -> Result<Self, SignatureError> {
+        let mut fields = tlv.contents();
+        fields.expect(der::INTEGER)?; // version
+        let mut id = fields.expect(der::SEQUENCE)?.contents();
+        let issuer = Name::parse(&id.expect(der::SEQUENCE)?)?;
+        let serial = id.expect(der::INTEGER)?.value.to_vec();
+        let digest_algorithm = hash_algorithm(&fields.expect(der::SEQUENCE)?)?;
+
+        // Authenticode and timestamps always sign attributes, never the
+        // content directly.
+        let attributes_tlv = fields
+            .optional(der::context(0))?
+            .ok_or_else(|| malformed("signer has no authenticated attributes"))?;
+        let mut signed_attributes = attributes_tlv.raw.to_vec();
+        signed_attributes[0] = der::SET;
+        let attributes = parse_attributes(&attributes_tlv)?;
+
+        let mut encryption = fields.expect(der::SEQUENCE)?.contents();
+        let encryption = der::oid_string(encryption.expect(der::OID)?.value)?;
+        if encryption != RSA_ENCRYPTION
+            && HashAlgorithm::from_rsa_signature_oid(&encryption).is_none()
+        {
+            return Err(SignatureError::UnsupportedAlgorithm(encryption));
+        }
+        let signature = fields.expect(der::OCTET_STRING)?.value.to_vec();
+        let unsigned_attributes = match fields.optional(der::context(1))? {
+            Some(tlv) => parse_attributes(&tlv)?,
+            None => Vec::new(),
+        };
+
+        Ok(SignerInfo {
+            issuer,
+            serial,
+            digest_algorithm,
+            signed_attributes,
+            attributes,
+            signature,
+            unsigned_attributes,
+        })
+    }
+
+    /// The contents of the single value of an authenticated attribute,
+    /// which must have `tag`.
+    fn attribute_value(&self, oid: &str, tag: u8) -> Result<Vec<u8>, SignatureError> {
+        let attribute = self
+            .attributes
+            .iter()
+            .find(|attribute| attribute.oid == oid && attribute.values.len() == 1)
+            .ok_or_else(|| SignatureError::Malformed(format!("attribute {} missing", oid)))?;
+        Ok(Reader::new(&attribute.values[0])
+            .expect(tag)?
+            .value
+            .to_vec())
+    }
+
+    pub fn unsigned_attribute(&self, oid: &str) -> Option<&Attribute> {
+        self.unsigned_attributes
+            .iter()
+            .find(|attribute| attribute.oid == oid)
+    }
+}
+
+fn parse_attributes(tlv: &Tlv) -> Result<Vec<Attribute>, DerError> {
+    let mut attributes = Vec::new();
+    let mut entries = tlv.contents();
+    while !entries.is_empty() {
+        let mut attribute = entries.expect(der::SEQUENCE)?.contents();
+        let oid = der::oid_string(attribute.expect(der::OID)?.value)?;
+        let mut set = attribute.expect(der::SET)?.contents();
+        let mut values = Vec::new();
+        while !set.is_empty() {
+            values.push(set.read()?.raw.to_vec());
+        }
+        attributes.push(Attribute { oid, values });
+    }
+    Ok(attributes)
+}
+
+/// The digest algorithm of an `AlgorithmIdentifier`.
+pub fn hash_algorithm(tlv: &Tlv) -> Result<HashAlgorithm, SignatureError> {
+    let oid = der::oid_string(tlv.contents().expect(der::OID)?.value)?;
+    HashAlgorithm::from_oid(&oid).ok_or(SignatureError::UnsupportedAlgorithm(oid))
+}
+
+fn malformed(reason: &str) -> SignatureError {
+    SignatureError::Malformed(reason.to_string())
+}
@@ -0,0 +1,631 @@
+//! Which certificates may sign the installer.
+//!
+//! A policy is a list of pins. Each names one certificate of the signing
+//! chain, the leaf, an intermediate or the root, by the SHA-256 of the
+//! certificate or of its public key, optionally together with the issuer
+//! that certificate must have, the name the signer must have and the dates
+//! the pin is accepted between. Rotating to a new signing certificate means
+//! adding its pin with a window that overlaps the old one's.
+//!
+//! Floorp's own pins are compiled in, see [`floorp_pins`]: the keys of the
+//! Certum CAs that issued its signing certificates, each for the years it
+//! did, together with the root it must chain to and a signer name Floorp
+//! releases are signed under. So are Microsoft's for the WebView2 Runtime
+//! bootstrapper, see [`microsoft_pins`].
+//!
+//! The policy also says how hard Windows should try to find out whether a
+//! certificate of the chain was revoked, and whether an installer is turned
+//! down when that cannot be found out, as happens offline or behind a
+//! captive portal.
+
+use super::cert::{Certificate, Name};
+use crate::checksum::parse_sha256;
+use crate::date::days_from_civil;
+use serde::Deserialize;
+use std::fmt;
+
+/// A CA that issued Floorp's signing certificates, see [`floorp_pins`].
+struct FloorpIssuer {
+    name: &'static str,
+    spki_sha256: &'static str,
+    /// The root the CA's certificate is issued by.
+    root: &'static str,
+    /// First and last day a certificate it issued to Floorp was valid on.
+    not_before: &'static str,
+    not_after: &'static str,
+}
+
+/// The issuing CAs of Floorp's signing certificates. Their keys are taken
+/// from the chains of signed releases; a CA that issues to anyone else too
+/// is only trusted for Floorp's signer names and for the years it signed
+/// for Floorp. A new certificate from another CA needs its entry here, with
+/// a window that overlaps the last one's.
+///
+/// None is listed until the chains of the signed releases are at hand, so
+/// the compiled-in policy accepts nothing and a policy has to be configured
+/// in `%LOCALAPPDATA%`. Pinning the Certum roots instead would accept
+/// anything Certum signs under these names.
+const FLOORP_ISSUERS: &[FloorpIssuer] = &[];
+
+/// SHA-256 thumbprint of Microsoft Root Certificate Authority 2011, which
+/// issues Microsoft Code Signing PCA 2011.
+const MICROSOFT_ROOT_2011: &str =
+    "847df6a78497943f27fc72eb93f9a637320a02b561d0a91b09e87a7807ed7c61";
+/// SHA-256 thumbprint of Microsoft Root Certificate Authority 2010.
+const MICROSOFT_ROOT_2010: &str =
+    "df545bf919a2439c36983b54cdfc903dfa4f37d3996d8d84b4c31eec6f3c163e";
+
+/// Name Microsoft signs its binaries under.
+const MICROSOFT_SIGNER: &str = "Microsoft Corporation";
+
+/// Names Floorp releases are signed under.
+const FLOORP_SIGNERS: [&str; 2] = [
+    "SignPath Foundation",
+    "Open Source Developer, Ryosuke Asano",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TrustPolicy {
+    /// Accepted certificates; one match anywhere in the chain is enough.
+    /// When the config leaves this empty, the compiled-in pins apply.
+    pub pins: Vec<SignerPin>,
+    pub revocation: RevocationCheck,
+    /// Rejects the installer when a cached check could not find out about
+    /// revocation, instead of going ahead and noting it in the report. An
+    /// online check always does, see [`TrustPolicy::requires_revocation`].
+    pub strict_revocation: bool,
+}
+
+/// How revocation of the signing chain is checked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevocationCheck {
+    /// Not checked at all.
+    Off,
+    /// Checked against what Windows has already cached, without going
+    /// online; fails when nothing is cached.
+    CachedOnly,
+    /// Checked for the whole chain, fetching lists and responses as needed.
+    #[default]
+    Online,
+}
+
+impl RevocationCheck {
+    /// The check to make for an installer that did not come over the
+    /// network, which may be installed without one.
+    pub fn without_network(self) -> Self {
+        match self {
+            RevocationCheck::Online => RevocationCheck::CachedOnly,
+            other => other,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SignerPin {
+    /// Shown in logs and errors.
+    pub label: String,
+    /// SHA-256 of the whole certificate (its thumbprint).
+    pub sha256: Option<String>,
+    /// SHA-256 of the SubjectPublicKeyInfo, which survives a renewal that
+    /// keeps the key.
+    pub spki_sha256: Option<String>,
+    /// Common name or full distinguished name the issuer of the pinned
+    /// certificate must have.
+    pub issuer: Option<String>,
+    /// Common name or full distinguished name the signer, the leaf of the
+    /// chain, must have. Needed when pinning a CA that also signs for others.
+    pub subject: Option<String>,
+    /// First day (`YYYY-MM-DD`, UTC) the pin is accepted on.
+    pub not_before: Option<String>,
+    /// Last day (`YYYY-MM-DD`, UTC) the pin is accepted on.
+    pub not_after: Option<String>,
+}
+
+/// The pins Floorp installers are accepted by, one per issuing CA and
+/// signer name.
+pub fn floorp_pins() -> Vec<SignerPin> {
+    issuer_pins(FLOORP_ISSUERS)
+}
+
+fn issuer_pins(issuers: &[FloorpIssuer]) -> Vec<SignerPin> {
+    issuers
+        .iter()
+        .flat_map(|issuer| {
+            FLOORP_SIGNERS.iter().map(move |signer| SignerPin {
+                label: format!("{} via {}", signer, issuer.name),
+                spki_sha256: Some(issuer.spki_sha256.to_string()),
+                issuer: Some(issuer.root.to_string()),
+                subject: Some(signer.to_string()),
+                not_before: Some(issuer.not_before.to_string()),
+                not_after: Some(issuer.not_after.to_string()),
+                ..SignerPin::default()
+            })
+        })
+        .collect()
+}
+
+/// The pins the WebView2 Runtime bootstrapper is accepted by: the roots of
+/// Microsoft's code signing chains, with Microsoft as the signer.
+pub fn microsoft_pins() -> Vec<SignerPin> {
+    [
+        (
+            "Microsoft Root Certificate Authority 2011",
+            MICROSOFT_ROOT_2011,
+        ),
+        (
+            "Microsoft Root Certificate Authority 2010",
+            MICROSOFT_ROOT_2010,
+        ),
+    ]
+    .iter()
+    .map(|(root, sha256)| SignerPin {
+        label: format!("{} via {}", MICROSOFT_SIGNER, root),
+        sha256: Some(sha256.to_string()),
+        subject: Some(MICROSOFT_SIGNER.to_string()),
+        ..SignerPin::default()
+    })
+    .collect()
+}
+
+impl TrustPolicy {
+    /// Puts `defaults` in place if the configured policy has no pins.
+    pub fn or_default_pins(mut self, defaults: impl FnOnce() -> Vec<SignerPin>) -> Self {
+        if self.pins.is_empty() {
+            self.pins = defaults();
+        }
+        self
+    }
+
+    /// Whether an installer is turned down when revocation could not be
+    /// checked. Whoever serves a binary signed with a revoked certificate
+    /// can usually block the lookup too, so an online check gives way only
+    /// to a cached one, as made for installers that did not come over the
+    /// network.
+    pub fn requires_revocation(&self) -> bool {
+        self.strict_revocation || self.revocation == RevocationCheck::Online
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for pin in &self.pins {
+            let hashes = [&pin.sha256, &pin.spki_sha256];
+            if hashes.iter().all(|hash| hash.is_none()) {
+                return Err(format!("pin {} needs sha256 or spkiSha256", pin.label));
+            }
+            if hashes
+                .iter()
+                .filter_map(|hash| hash.as_deref())
+                .any(|hash| parse_sha256(hash).is_none())
+            {
+                return Err(format!("pin {} has an invalid SHA-256", pin.label));
+            }
+            for date in [&pin.not_before, &pin.not_after].into_iter().flatten() {
+                if parse_date(date).is_none() {
+                    return Err(format!("pin {} has an invalid date: {}", pin.label, date));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The pin that accepted a chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PinMatch {
+    pub label: String,
+    /// Position of the pinned certificate, `0` being the leaf.
+    pub depth: usize,
+}
+
+/// Why a chain was not accepted. When a pinned certificate was found but
+/// failed a constraint, that constraint is reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PolicyError {
+    /// The policy has no pins at all.
+    NoPins,
+    /// No certificate of the chain is pinned.
+    NotPinned,
+    IssuerMismatch {
+        label: String,
+        expected: String,
+        found: String,
+    },
+    /// The pinned certificate is in the chain, but the signer is someone
+    /// else it signs for.
+    SubjectMismatch {
+        label: String,
+        expected: String,
+        found: String,
+    },
+    /// The pin is not accepted at the time of the check.
+    OutsideWindow { label: String },
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::NoPins => f.write_str("no signer pins configured"),
+            PolicyError::NotPinned => f.write_str("no certificate in the chain is pinned"),
+            PolicyError::IssuerMismatch {
+                label,
+                expected,
+                found,
+            } => write!(f, "{}: issued by {}, expected {}", label, found, expected),
+            PolicyError::SubjectMismatch {
+                label,
+                expected,
+                found,
+            } => write!(f, "{}: signed by {}, expected {}", label, found, expected),
+            PolicyError::OutsideWindow { label } => {
+                write!(f, "{}: pin is not valid at this time", label)
+            }
+        }
+    }
+}
+
+/// Checks `chain`, ordered from the leaf upwards, against `policy` at Unix
+/// time `now`. Only the part of the chain whose issuer names link up is
+/// considered; checking the signatures is up to the caller, as is adding
+/// the root, see [`complete_chain`](super::cert::complete_chain).
+pub fn evaluate(
+    policy: &TrustPolicy,
+    chain: &[Certificate],
+    now: i64,
+) -> Result<PinMatch, PolicyError> {
+    if policy.pins.is_empty() {
+        return Err(PolicyError::NoPins);
+    }
+
+    let linked = chain
+        .windows(2)
+        .position(|pair| !pair[0].names_issuer(&pair[1]))
+        .map_or(chain.len(), |broken| broken + 1);
+
+    let mut near_miss = None;
+    for (depth, cert) in chain[..linked].iter().enumerate() {
+        for pin in policy.pins.iter().filter(|pin| pin_matches_key(pin, cert)) {
+            match check_constraints(pin, cert, &chain[0], now) {
+                Ok(()) => {
+                    return Ok(PinMatch {
+                        label: pin.label.clone(),
+                        depth,
+                    })
+                }
+                Err(e) => {
+                    near_miss.get_or_insert(e);
+                }
+            }
+        }
+    }
+    Err(near_miss.unwrap_or(PolicyError::NotPinned))
+}
+
+fn pin_matches_key(pin: &SignerPin, cert: &Certificate) -> bool {
+    let same = |pinned: &Option<String>, actual: String| {
+        pinned.as_deref().and_then(parse_sha256) == Some(actual)
+    };
+    same(&pin.sha256, cert.thumbprint()) || same(&pin.spki_sha256, cert.spki_sha256())
+}
+
+fn check_constraints(
+    pin: &SignerPin,
+    cert: &Certificate,
+    signer: &Certificate,
+    now: i64,
+) -> Result<(), PolicyError> {
+    if let Some(expected) = &pin.issuer {
+        if !names_match(&cert.issuer, expected) {
+            return Err(PolicyError::IssuerMismatch {
+                label: pin.label.clone(),
+                expected: expected.clone(),
+                found: cert.issuer.to_string(),
+            });
+        }
+    }
+    if let Some(expected) = &pin.subject {
+        if !names_match(&signer.subject, expected) {
+            return Err(PolicyError::SubjectMismatch {
+                label: pin.label.clone(),
+                expected: expected.clone(),
+                found: signer.subject.to_string(),
+            });
+        }
+    }
+
+    let not_before = pin.not_before.as_deref().and_then(parse_date);
+    // The last day counts in full.
+    let not_after = pin
+        .not_after
+        .as_deref()
+        .and_then(parse_date)
+        .map(|day| day + 86_400);
+    if not_before.is_some_and(|start| now < start) || not_after.is_some_and(|end| now >= end) {
+        return Err(PolicyError::OutsideWindow {
+            label: pin.label.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Whether `name` has `expected` as its common name or in full.
+fn names_match(name: &Name, expected: &str) -> bool {
+    name.common_name() == Some(expected) || name.to_string() == expected
+}
+
+/// Parses `YYYY-MM-DD` to the Unix time of its start.
+fn parse_date(value: &str) -> Option<i64> {
+    let mut parts = value.split('-');
+    let year: i64 = parts.next().filter(|part| part.len() == 4)?.parse().ok()?;
+    let month: i64 = parts.next().filter(|part| part.len() == 2)?.parse().ok()?;
+    let day: i64 = parts.next().filter(|part| part.len() == 2)?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::cert::build_chain;
+    use crate::signing::fixtures;
+
+    /// 2025-06-01, inside the validity of the fixture leaf.
+    const NOW: i64 = 1_748_736_000;
+
+    fn chain() -> Vec<Certificate> {
+        build_chain(
+            fixtures::leaf(),
+            &[fixtures::intermediate(), fixtures::root()],
+        )
+    }
+
+    fn spki_pin(label: &str, spki_sha256: &str) -> SignerPin {
+        SignerPin {
+            label: label.to_string(),
+            spki_sha256: Some(spki_sha256.to_string()),
+            ..SignerPin::default()
+        }
+    }
+
+    fn policy_unchecked(pins: Vec<SignerPin>) -> TrustPolicy {
+        TrustPolicy {
+            pins,
+            ..TrustPolicy::default()
+        }
+    }
+
+    fn policy(pins: Vec<SignerPin>) -> TrustPolicy {
+        let policy = policy_unchecked(pins);
+        policy.validate().unwrap();
+        policy
+    }
+
+    #[test]
+    fn accepts_a_pinned_leaf_or_intermediate() {
+        let by_thumbprint = policy(vec![SignerPin {
+            label: "leaf".to_string(),
+            sha256: Some(fixtures::leaf().thumbprint().to_uppercase()),
+            ..SignerPin::default()
+        }]);
+        assert_eq!(
+            evaluate(&by_thumbprint, &chain(), NOW),
+            Ok(PinMatch {
+                label: "leaf".to_string(),
+                depth: 0
+            })
+        );
+
+        let by_intermediate = policy(vec![spki_pin("ca", fixtures::INTERMEDIATE_SPKI_SHA256)]);
+        assert_eq!(evaluate(&by_intermediate, &chain(), NOW).unwrap().depth, 1);
+    }
+
+    #[test]
+    fn rejects_a_lookalike_name_from_another_ca() {
+        let policy = policy(vec![
+            spki_pin("leaf", fixtures::LEAF_SPKI_SHA256),
+            spki_pin("ca", fixtures::INTERMEDIATE_SPKI_SHA256),
+        ]);
+        let impostor = build_chain(fixtures::impostor(), &[fixtures::other_root()]);
+
+        assert_eq!(
+            evaluate(&policy, &impostor, NOW),
+            Err(PolicyError::NotPinned)
+        );
+        assert_eq!(
+            evaluate(&TrustPolicy::default(), &chain(), NOW),
+            Err(PolicyError::NoPins)
+        );
+    }
+
+    #[test]
+    fn ignores_certificates_that_do_not_chain_to_the_leaf() {
+        let policy = policy(vec![spki_pin("ca", fixtures::INTERMEDIATE_SPKI_SHA256)]);
+        let unlinked = [fixtures::impostor(), fixtures::intermediate()];
+
+        assert_eq!(
+            evaluate(&policy, &unlinked, NOW),
+            Err(PolicyError::NotPinned)
+        );
+    }
+
+    #[test]
+    fn enforces_the_issuer_constraint() {
+        let mut pin = spki_pin("leaf", fixtures::LEAF_SPKI_SHA256);
+        pin.issuer = Some("Fixture Code Signing CA".to_string());
+        assert!(evaluate(&policy(vec![pin.clone()]), &chain(), NOW).is_ok());
+
+        pin.issuer = Some("CN=Fixture Code Signing CA, O=Fixture Trust".to_string());
+        assert!(evaluate(&policy(vec![pin.clone()]), &chain(), NOW).is_ok());
+
+        pin.issuer = Some("Fixture Code Signing".to_string());
+        assert_eq!(
+            evaluate(&policy(vec![pin]), &chain(), NOW),
+            Err(PolicyError::IssuerMismatch {
+                label: "leaf".to_string(),
+                expected: "Fixture Code Signing".to_string(),
+                found: "CN=Fixture Code Signing CA, O=Fixture Trust".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn enforces_the_subject_constraint() {
+        let mut pin = SignerPin {
+            label: "root".to_string(),
+            sha256: Some(fixtures::root().thumbprint()),
+            subject: Some("SignPath Foundation".to_string()),
+            ..SignerPin::default()
+        };
+        assert_eq!(
+            evaluate(&policy(vec![pin.clone()]), &chain(), NOW)
+                .unwrap()
+                .depth,
+            2
+        );
+
+        pin.subject = Some("Microsoft Corporation".to_string());
+        assert_eq!(
+            evaluate(&policy(vec![pin]), &chain(), NOW),
+            Err(PolicyError::SubjectMismatch {
+                label: "root".to_string(),
+                expected: "Microsoft Corporation".to_string(),
+                found: "CN=SignPath Foundation, O=SignPath Foundation".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn pins_the_floorp_issuers_for_their_years() {
+        let issuers = [FloorpIssuer {
+            name: "Fixture Code Signing CA",
+            spki_sha256: fixtures::INTERMEDIATE_SPKI_SHA256,
+            root: "Fixture Root CA",
+            not_before: "2025-01-01",
+            not_after: "2025-12-31",
+        }];
+        let fixture = policy(issuer_pins(&issuers));
+        assert_eq!(fixture.pins.len(), FLOORP_SIGNERS.len());
+        assert_eq!(
+            evaluate(&fixture, &chain(), NOW),
+            Ok(PinMatch {
+                label: "SignPath Foundation via Fixture Code Signing CA".to_string(),
+                depth: 1
+            })
+        );
+        // 2026-01-01
+        assert!(matches!(
+            evaluate(&fixture, &chain(), 1_767_225_600),
+            Err(PolicyError::OutsideWindow { .. })
+        ));
+        let elsewhere = [FloorpIssuer {
+            root: "Certum Trusted Network CA 2",
+            ..issuers[0]
+        }];
+        assert!(matches!(
+            evaluate(&policy(issuer_pins(&elsewhere)), &chain(), NOW),
+            Err(PolicyError::IssuerMismatch { .. })
+        ));
+
+        let policy = TrustPolicy::default().or_default_pins(floorp_pins);
+        policy.validate().unwrap();
+        assert!(policy.pins.iter().all(|pin| pin.issuer.is_some()
+            && pin.subject.is_some()
+            && pin.not_before.is_some()
+            && pin.not_after.is_some()));
+
+        let configured = policy_unchecked(vec![spki_pin("ca", fixtures::INTERMEDIATE_SPKI_SHA256)]);
+        assert_eq!(configured.clone().or_default_pins(floorp_pins), configured);
+    }
+
+    #[test]
+    fn rejects_a_microsoft_signer_from_another_root() {
+        let microsoft = TrustPolicy::default().or_default_pins(microsoft_pins);
+        microsoft.validate().unwrap();
+        assert_eq!(microsoft.pins.len(), 2);
+
+        let counterfeit = build_chain(
+            fixtures::counterfeit_microsoft(),
+            &[fixtures::counterfeit_root()],
+        );
+        assert_eq!(counterfeit.len(), 2);
+        assert_eq!(
+            evaluate(&microsoft, &counterfeit, NOW),
+            Err(PolicyError::NotPinned)
+        );
+
+        // The signer name is only checked once the pinned root is found.
+        let anchored = policy(vec![SignerPin {
+            label: "root".to_string(),
+            sha256: Some(fixtures::root().thumbprint()),
+            subject: Some("Microsoft Corporation".to_string()),
+            ..SignerPin::default()
+        }]);
+        assert_eq!(
+            evaluate(&anchored, &counterfeit, NOW),
+            Err(PolicyError::NotPinned)
+        );
+    }
+
+    #[test]
+    fn rotates_between_pins_by_their_windows() {
+        let mut old = spki_pin("2024 key", fixtures::LEAF_SPKI_SHA256);
+        old.not_after = Some("2025-12-31".to_string());
+        let mut new = spki_pin("2026 key", fixtures::INTERMEDIATE_SPKI_SHA256);
+        new.not_before = Some("2025-12-01".to_string());
+        let policy = policy(vec![old, new]);
+
+        let label = |now| evaluate(&policy, &chain(), now).map(|found| found.label);
+        assert_eq!(label(NOW), Ok("2024 key".to_string()));
+        // 2025-12-15: both accepted, the leaf pin is found first.
+        assert_eq!(label(1_765_756_800), Ok("2024 key".to_string()));
+        // 2026-01-01: only the new pin remains.
+        assert_eq!(label(1_767_225_600), Ok("2026 key".to_string()));
+        // 2025-01-01: the new pin is not yet accepted, the old one still is.
+        assert_eq!(label(1_735_689_600), Ok("2024 key".to_string()));
+
+        let mut expired = spki_pin("2024 key", fixtures::LEAF_SPKI_SHA256);
+        expired.not_after = Some("2025-01-01".to_string());
+        assert_eq!(
+            evaluate(&policy_unchecked(vec![expired]), &chain(), NOW),
+            Err(PolicyError::OutsideWindow {
+                label: "2024 key".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn validates_pins() {
+        let mut pin = spki_pin("leaf", "not a digest");
+        assert!(policy_unchecked(vec![pin.clone()]).validate().is_err());
+
+        pin.spki_sha256 = None;
+        assert!(policy_unchecked(vec![pin.clone()]).validate().is_err());
+
+        pin.sha256 = Some(fixtures::leaf().thumbprint());
+        pin.not_before = Some("2025-13-01".to_string());
+        assert!(policy_unchecked(vec![pin]).validate().is_err());
+        assert_eq!(parse_date("2025-06-01"), Some(NOW));
+    }
+
+    #[test]
+    fn reads_the_revocation_setting() {
+        let policy: TrustPolicy = serde_json::from_str(r#"{"revocation": "cachedOnly"}"#).unwrap();
+        assert_eq!(policy.revocation, RevocationCheck::CachedOnly);
+        assert_eq!(TrustPolicy::default().revocation, RevocationCheck::Online);
+        assert!(TrustPolicy::default().requires_revocation());
+        assert!(!policy.requires_revocation());
+        let policy: TrustPolicy =
+            serde_json::from_str(r#"{"revocation": "cachedOnly", "strictRevocation": true}"#)
+                .unwrap();
+        assert!(policy.requires_revocation());
+        assert_eq!(
+            RevocationCheck::Online.without_network(),
+            RevocationCheck::CachedOnly
+        );
+        assert_eq!(RevocationCheck::Off.without_network(), RevocationCheck::Off);
+        assert!(serde_json::from_str::<TrustPolicy>(r#"{"revocation": "sometimes"}"#).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! What the signature check found, for the error screen and the log.
+//!
+//! When an installer is rejected, the report says who signed it, when it
+//! was timestamped, what Windows made of it and which rule turned it down,
+//! so that a user can pass it on and support can tell a broken download
+//! from an unexpected signer or a revocation server that was out of reach.
+
+use super::authenticode::{Signature, SignatureError};
+use super::der::format_time;
+use serde::Serialize;
+use std::sync::Mutex;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureReport {
+    /// The result of `WinVerifyTrust`, by name where it has one.
+    pub windows_status: Option<String>,
+    pub subject: Option<String>,
+    pub issuer: Option<String>,
+    /// Serial number of the signer's certificate, in hex.
+    pub serial: Option<String>,
+    /// SHA-256 of the signer's certificate.
+    pub thumbprint: Option<String>,
+    pub timestamp: Option<String>,
+    pub timestamp_authority: Option<String>,
+    /// Label of the pin the signer was accepted by.
+    pub pin: Option<String>,
+    /// Why revocation could not be checked, when the installer was let
+    /// through regardless.
+    pub revocation_unknown: Option<String>,
+    /// The first rule the installer did not pass, if any.
+    pub failure: Option<RuleFailure>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleFailure {
+    pub rule: Rule,
+    pub reason: String,
+}
+
+/// The checks an installer goes through, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Rule {
+    /// The signature is there and matches the file and its certificates.
+    Signature,
+    /// The timestamp verifies and falls within the signer's certificate.
+    Timestamp,
+    /// Without a timestamp, the signer's certificate is valid now.
+    Validity,
+    /// `WinVerifyTrust` accepts the file.
+    Windows,
+    /// No certificate of the chain is known to be revoked.
+    Revocation,
+    /// The chain matches a pin of the trust policy.
+    Pinning,
+}
+
+impl SignatureReport {
+    /// Fills in the signer and timestamp of a signature that checked out.
+    pub fn record_signature(&mut self, signature: &Signature) {
+        if let Some(signer) = signature.chain.first() {
+            self.subject = Some(signer.subject.to_string());
+            self.issuer = Some(signer.issuer.to_string());
+            self.serial = Some(hex::encode(&signer.serial));
+            self.thumbprint = Some(signer.thumbprint());
+        }
+        if let Some(timestamp) = &signature.timestamp {
+            self.timestamp = Some(format_time(timestamp.time));
+            self.timestamp_authority = Some(timestamp.authority.clone());
+        }
+    }
+
+    pub fn fail(&mut self, rule: Rule, reason: impl Into<String>) {
+        self.failure = Some(RuleFailure {
+            rule,
+            reason: reason.into(),
+        });
+    }
+
+    /// Records why the portable signature check rejected the file.
+    pub fn reject(&mut self, error: &SignatureError) {
+        let rule = match error {
+            SignatureError::TimestampInvalid(_)
+            | SignatureError::TimestampOutsideValidity { .. } => Rule::Timestamp,
+            SignatureError::CertificateExpired { .. } => Rule::Validity,
+            _ => Rule::Signature,
+        };
+        self.fail(rule, error.to_string());
+    }
+}
+
+/// The last report of this run, kept for the error screen to ask for.
+#[derive(Default)]
+pub struct SignatureReports {
+    last: Mutex<Option<SignatureReport>>,
+}
+
+impl SignatureReports {
+    pub fn record(&self, report: SignatureReport) {
+        *self.last.lock().unwrap() = Some(report);
+    }
+
+    pub fn clear(&self) {
+        *self.last.lock().unwrap() = None;
+    }
+
+    pub fn last(&self) -> Option<SignatureReport> {
+        self.last.lock().unwrap().clone()
+    }
+}
+
+/// Names a `WinVerifyTrust` result, keeping its code alongside.
+pub fn win_verify_trust_status(code: i32) -> String {
+    let name = match code as u32 {
+        0 => "ERROR_SUCCESS",
+        0x800B_0001 => "TRUST_E_PROVIDER_UNKNOWN",
+        0x800B_0003 => "TRUST_E_SUBJECT_FORM_UNKNOWN",
+        0x800B_0004 => "TRUST_E_SUBJECT_NOT_TRUSTED",
+        0x800B_0100 => "TRUST_E_NOSIGNATURE",
+        0x800B_0101 => "CERT_E_EXPIRED",
+        0x800B_0109 => "CERT_E_UNTRUSTEDROOT",
+        0x800B_010A => "CERT_E_CHAINING",
+        0x800B_010C => "CERT_E_REVOKED",
+        0x800B_010E => "CERT_E_REVOCATION_FAILURE",
+        0x800B_0110 => "CERT_E_WRONG_USAGE",
+        0x800B_0111 => "TRUST_E_EXPLICIT_DISTRUST",
+        0x8009_6005 => "TRUST_E_TIME_STAMP",
+        0x8009_6010 => "TRUST_E_BAD_DIGEST",
+        0x8009_2010 => "CRYPT_E_REVOKED",
+        0x8009_2012 => "CRYPT_E_NO_REVOCATION_CHECK",
+        0x8009_2013 => "CRYPT_E_REVOCATION_OFFLINE",
+        0x8009_2026 => "CRYPT_E_SECURITY_SETTINGS",
+        _ => return format!("{:#010X}", code as u32),
+    };
+    format!("{} ({:#010X})", name, code as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{fixtures, verify};
+    use std::io::Cursor;
+
+    #[test]
+    fn names_win_verify_trust_results() {
+        assert_eq!(win_verify_trust_status(0), "ERROR_SUCCESS (0x00000000)");
+        assert_eq!(
+            win_verify_trust_status(0x800B_0109_u32 as i32),
+            "CERT_E_UNTRUSTEDROOT (0x800B0109)"
+        );
+        assert_eq!(
+            win_verify_trust_status(0x8000_4005_u32 as i32),
+            "0x80004005"
+        );
+    }
+
+    #[test]
+    fn reports_the_signer_and_the_failed_rule() {
+        let signature = verify(
+            &mut Cursor::new(fixtures::signed_image()),
+            1_756_684_800,
+            &[fixtures::root()],
+        )
+        .unwrap();
+        let mut report = SignatureReport::default();
+        report.record_signature(&signature);
+        report.fail(Rule::Pinning, "no certificate in the chain is pinned");
+
+        assert_eq!(
+            report.subject.as_deref(),
+            Some("CN=SignPath Foundation, O=SignPath Foundation")
+        );
+        assert_eq!(report.timestamp.as_deref(), Some("2025-06-01 12:00:00 UTC"));
+        assert_eq!(
+            report.thumbprint.as_deref(),
+            Some(signature.chain[0].thumbprint().as_str())
+        );
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["timestampAuthority"],
+            "CN=Fixture Timestamp Authority, O=Fixture Trust"
+        );
+        assert_eq!(json["failure"]["rule"], "pinning");
+        assert!(json["windowsStatus"].is_null());
+        assert!(json["revocationUnknown"].is_null());
+
+        let mut report = SignatureReport::default();
+        report.reject(&SignatureError::CertificateExpired {
+            not_before: 0,
+            not_after: 0,
+        });
+        assert_eq!(report.failure.unwrap().rule, Rule::Validity);
+    }
+}
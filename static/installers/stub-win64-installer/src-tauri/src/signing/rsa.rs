@@ -0,0 +1,209 @@
+//! RSA PKCS #1 v1.5 signature verification.
+//!
+//! Only public keys are ever used, so the arithmetic is the plain
+//! schoolbook kind: nothing here handles secrets that timing could leak.
+
+use super::der::{self, DerError, Reader};
+use super::hash::HashAlgorithm;
+use std::cmp::Ordering;
+
+pub const RSA_ENCRYPTION: &str = "1.2.840.113549.1.1.1";
+
+/// An unsigned integer as little-endian 32-bit limbs, without leading zero
+/// limbs.
+type Limbs = Vec<u32>;
+
+#[derive(Clone, Debug)]
+pub struct PublicKey {
+    modulus: Limbs,
+    exponent: Limbs,
+    /// Length of the modulus, and so of signatures, in bytes.
+    len: usize,
+}
+
+impl PublicKey {
+    /// Reads an RSA key from a DER `SubjectPublicKeyInfo`. Returns `None`
+    /// for keys of other algorithms.
+    pub fn from_spki(spki: &[u8]) -> Result<Option<Self>, DerError> {
+        let mut fields = Reader::new(spki).expect(der::SEQUENCE)?.contents();
+        let mut algorithm = fields.expect(der::SEQUENCE)?.contents();
+        if der::oid_string(algorithm.expect(der::OID)?.value)? != RSA_ENCRYPTION {
+            return Ok(None);
+        }
+        let bits = fields.expect(der::BIT_STRING)?.value;
+        let key = match bits.split_first() {
+            Some((0, key)) => key,
+            _ => return Err(DerError("invalid public key".to_string())),
+        };
+        let mut key = Reader::new(key).expect(der::SEQUENCE)?.contents();
+        let modulus = from_be_bytes(key.expect(der::INTEGER)?.value);
+        let exponent = from_be_bytes(key.expect(der::INTEGER)?.value);
+        if modulus.is_empty() || exponent.is_empty() {
+            return Err(DerError("invalid public key".to_string()));
+        }
+
+        let len = bit_len(&modulus).div_ceil(8);
+        Ok(Some(PublicKey {
+            modulus,
+            exponent,
+            len,
+        }))
+    }
+
+    /// Whether `signature` is this key's signature over a message whose
+    /// `algorithm` digest is `digest`.
+    pub fn verify(&self, algorithm: HashAlgorithm, digest: &[u8], signature: &[u8]) -> bool {
+        if signature.len() != self.len {
+            return false;
+        }
+        let signature = from_be_bytes(signature);
+        if compare(&signature, &self.modulus) != Ordering::Less {
+            return false;
+        }
+        let encoded = to_be_bytes(
+            &mod_pow(&signature, &self.exponent, &self.modulus),
+            self.len,
+        );
+
+        // 00 01 FF .. FF 00 DigestInfo, with at least eight bytes of padding.
+        let prefix = algorithm.digest_info_prefix();
+        let info_len = prefix.len() + digest.len();
+        if self.len < info_len + 11 {
+            return false;
+        }
+        let padding = self.len - info_len - 3;
+        let mut expected = vec![0x00, 0x01];
+        expected.extend(std::iter::repeat_n(0xff, padding));
+        expected.push(0x00);
+        expected.extend_from_slice(prefix);
+        expected.extend_from_slice(digest);
+        encoded == expected
+    }
+}
+
+fn from_be_bytes(bytes: &[u8]) -> Limbs {
+    let mut limbs: Limbs = bytes
+        .rchunks(4)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(0u32, |limb, &byte| (limb << 8) | u32::from(byte))
+        })
+        .collect();
+    trim(&mut limbs);
+    limbs
+}
+
+fn to_be_bytes(limbs: &[u32], len: usize) -> Vec<u8> {
+    let mut bytes = vec![0; len];
+    for (i, limb) in limbs.iter().enumerate() {
+        for (j, byte) in limb.to_le_bytes().into_iter().enumerate() {
+            if let Some(pos) = len.checked_sub(i * 4 + j + 1) {
+                bytes[pos] = byte;
+            }
+        }
+    }
+    bytes
+}
+
+fn trim(limbs: &mut Limbs) {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+}
+
+fn bit_len(limbs: &[u32]) -> usize {
+    limbs
+        .last()
+        .map_or(0, |top| limbs.len() * 32 - top.leading_zeros() as usize)
+}
+
+fn compare(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn mul(a: &[u32], b: &[u32]) -> Limbs {
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let sum = u64::from(product[i + j]) + u64::from(x) * u64::from(y) + carry;
+            product[i + j] = sum as u32;
+            carry = sum >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+    trim(&mut product);
+    product
+}
+
+/// `a` modulo `m`, by shifting `a` in one bit at a time.
+fn rem(a: &[u32], m: &[u32]) -> Limbs {
+    let mut remainder = Limbs::with_capacity(m.len() + 1);
+    for bit in (0..bit_len(a)).rev() {
+        let mut carry = (a[bit / 32] >> (bit % 32)) & 1;
+        for limb in remainder.iter_mut() {
+            let next = *limb >> 31;
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            remainder.push(carry);
+        }
+        if compare(&remainder, m) != Ordering::Less {
+            sub_assign(&mut remainder, m);
+        }
+    }
+    remainder
+}
+
+/// `a -= b`, where `a >= b`.
+fn sub_assign(a: &mut Limbs, b: &[u32]) {
+    let mut borrow = 0u64;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let subtrahend = u64::from(b.get(i).copied().unwrap_or(0)) + borrow;
+        let current = u64::from(*limb);
+        if current >= subtrahend {
+            *limb = (current - subtrahend) as u32;
+            borrow = 0;
+        } else {
+            *limb = (current + (1 << 32) - subtrahend) as u32;
+            borrow = 1;
+        }
+    }
+    trim(a);
+}
+
+fn mod_pow(base: &[u32], exponent: &[u32], modulus: &[u32]) -> Limbs {
+    let mut result = rem(&[1], modulus);
+    for bit in (0..bit_len(exponent)).rev() {
+        result = rem(&mul(&result, &result), modulus);
+        if (exponent[bit / 32] >> (bit % 32)) & 1 == 1 {
+            result = rem(&mul(&result, base), modulus);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn does_modular_arithmetic() {
+        let n = from_be_bytes(&[0x01, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(n, vec![1, 1]);
+        assert_eq!(to_be_bytes(&n, 6), [0, 0x01, 0, 0, 0, 0x01]);
+
+        // 3^200 mod 1_000_000_007
+        let result = mod_pow(
+            &[3],
+            &[200],
+            &from_be_bytes(&1_000_000_007u32.to_be_bytes()),
+        );
+        assert_eq!(result, vec![136_318_165]);
+        assert_eq!(rem(&mul(&[u32::MAX, u32::MAX], &[2]), &[7]), vec![2]);
+    }
+}
@@ -0,0 +1,99 @@
+//! RFC 3161 timestamps countersigning an Authenticode signature.
+//!
+//! A timestamp authority signs the digest of the signature together with
+//! the time it saw it, which lets a signature outlive its certificate: it
+//! was made while the certificate was valid. That only holds for an
+//! authority one trusts, so its certificate has to be for time stamping and
+//! its chain has to end in one of the trusted roots.
+
+use super::authenticode::SignatureError;
+use super::cert::{complete_chain, Certificate, TIME_STAMPING};
+use super::der::{self, Reader};
+use super::pkcs7::{hash_algorithm, SignedData, SignerInfo};
+
+/// The unsigned attribute Authenticode keeps RFC 3161 timestamps in.
+const RFC3161_COUNTERSIGNATURE: &str = "1.3.6.1.4.1.311.3.3.1";
+const TST_INFO: &str = "1.2.840.113549.1.9.16.1.4";
+
+/// A timestamp that checked out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timestamp {
+    /// When the signature was made, in Unix seconds.
+    pub time: i64,
+    /// Subject of the timestamp authority's certificate.
+    pub authority: String,
+}
+
+/// Checks the timestamp countersigning `signer`, if there is one: that its
+/// token is signed by a certificate it includes, which chains to one of
+/// `roots`, that it is about the signer's signature, and that it was made
+/// while that certificate was valid.
+pub fn verify(
+    signer: &SignerInfo,
+    roots: &[Certificate],
+) -> Result<Option<Timestamp>, SignatureError> {
+    let Some(attribute) = signer.unsigned_attribute(RFC3161_COUNTERSIGNATURE) else {
+        return Ok(None);
+    };
+    let token = match attribute.values.as_slice() {
+        [token] => token,
+        _ => return Err(invalid("expected a single timestamp token")),
+    };
+    verify_token(token, &signer.signature, roots)
+        .map(Some)
+        .map_err(|e| match e {
+            SignatureError::TimestampInvalid(_) | SignatureError::Io(_) => e,
+            e => invalid(&e.to_string()),
+        })
+}
+
+fn verify_token(
+    token: &[u8],
+    signature: &[u8],
+    roots: &[Certificate],
+) -> Result<Timestamp, SignatureError> {
+    let signed = SignedData::parse(token)?;
+    if signed.content_type != TST_INFO {
+        return Err(invalid("not a timestamp token"));
+    }
+    let authority = signed.verify_signer()?;
+    if !authority.has_extended_key_usage(TIME_STAMPING) {
+        return Err(invalid(
+            "the timestamp authority's certificate is not for time stamping",
+        ));
+    }
+    let chain = complete_chain(signed.chain(authority)?, roots);
+    let anchor = chain.last().unwrap();
+    if !roots.iter().any(|root| root.der() == anchor.der()) {
+        return Err(invalid(
+            "the timestamp authority does not chain to a trusted root",
+        ));
+    }
+
+    let mut info = Reader::new(&signed.content)
+        .expect(der::SEQUENCE)?
+        .contents();
+    info.expect(der::INTEGER)?; // version
+    info.expect(der::OID)?; // policy
+    let mut imprint = info.expect(der::SEQUENCE)?.contents();
+    let algorithm = hash_algorithm(&imprint.expect(der::SEQUENCE)?)?;
+    if imprint.expect(der::OCTET_STRING)?.value != algorithm.digest(signature) {
+        return Err(invalid("the timestamp is for a different signature"));
+    }
+    info.expect(der::INTEGER)?; // serial number
+    let time = der::time(&info.expect(der::GENERALIZED_TIME)?)?;
+
+    if time < authority.not_before || time > authority.not_after {
+        return Err(invalid(
+            "the timestamp authority's certificate was not valid at that time",
+        ));
+    }
+    Ok(Timestamp {
+        time,
+        authority: authority.subject.to_string(),
+    })
+}
+
+fn invalid(reason: &str) -> SignatureError {
+    SignatureError::TimestampInvalid(reason.to_string())
+}
@@ -0,0 +1,176 @@
+//! A private directory for the files of one install run.
+//!
+//! The installer, its `/INI` config and the script for the elevated run used
+//! to sit in `%TEMP%` under fixed names, where another process could plant
+//! or swap them between verification and the run. Each run now gets a
+//! randomly named directory only the user (and SYSTEM and administrators)
+//! can open, created fresh so an existing one is never reused. It is removed
+//! when the [`Workspace`] is dropped, which covers success, errors,
+//! cancellation and panics alike.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DIR_PREFIX: &str = "floorp-installer-";
+
+/// Attempts at finding an unused name before giving up.
+const CREATE_ATTEMPTS: usize = 8;
+
+#[derive(Debug)]
+pub struct Workspace {
+    dir: PathBuf,
+}
+
+impl Workspace {
+    /// Creates a workspace in the user's temp directory.
+    pub fn create() -> io::Result<Self> {
+        Workspace::create_in(&env::temp_dir())
+    }
+
+    pub fn create_in(parent: &Path) -> io::Result<Self> {
+        for _ in 0..CREATE_ATTEMPTS {
+            let dir = parent.join(format!("{}{}", DIR_PREFIX, random_suffix()?));
+            match create_private_dir(&dir) {
+                Ok(()) => {
+                    println!("[INFO] Created workspace: {}", dir.display());
+                    return Ok(Workspace { dir });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "no unused workspace name found",
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The path of the file called `name` inside the workspace.
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        match fs::remove_dir_all(&self.dir) {
+            Ok(()) => println!("[INFO] Removed workspace: {}", self.dir.display()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => println!(
+                "[WARN] Failed to remove workspace {}: {}",
+                self.dir.display(),
+                e
+            ),
+        }
+    }
+}
+
+fn random_suffix() -> io::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Creates `dir`, failing if it exists, readable by its owner only.
+#[cfg(unix)]
+pub(crate) fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+/// Creates `dir`, failing if it exists, with a protected DACL granting
+/// access to its owner, SYSTEM and administrators only. The elevated
+/// installer runs either as the same user or as an administrator.
+#[cfg(windows)]
+pub(crate) fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::iter::once;
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+    use windows::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+    use windows::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+    use windows::Win32::Storage::FileSystem::CreateDirectoryW;
+
+    // Win32 errors come wrapped in an HRESULT, with the code in the low word.
+    let win32_error = |e: windows::core::Error| io::Error::from_raw_os_error(e.code().0 & 0xFFFF);
+
+    let mut descriptor = PSECURITY_DESCRIPTOR::default();
+    unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            w!("D:P(A;OICI;FA;;;OW)(A;OICI;FA;;;SY)(A;OICI;FA;;;BA)"),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            None,
+        )
+    }
+    .map_err(win32_error)?;
+
+    let attributes = SECURITY_ATTRIBUTES {
+        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: descriptor.0,
+        bInheritHandle: false.into(),
+    };
+    let path: Vec<u16> = dir.as_os_str().encode_wide().chain(once(0)).collect();
+    let created = unsafe { CreateDirectoryW(PCWSTR(path.as_ptr()), Some(&attributes)) };
+    unsafe {
+        LocalFree(HLOCAL(descriptor.0));
+    }
+    created.map_err(win32_error)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creates_a_fresh_private_directory_and_removes_it_on_drop() {
+        let parent = tempfile::tempdir().unwrap();
+        let first = Workspace::create_in(parent.path()).unwrap();
+        let second = Workspace::create_in(parent.path()).unwrap();
+        assert_ne!(first.path(), second.path());
+
+        let name = first.path().file_name().unwrap().to_str().unwrap();
+        assert_eq!(name.len(), DIR_PREFIX.len() + 32);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(first.path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        let installer = first.file("setup.exe");
+        fs::write(&installer, b"installer").unwrap();
+        let dir = first.path().to_path_buf();
+        drop(first);
+        assert!(!dir.exists());
+        assert!(second.path().is_dir());
+    }
+
+    #[test]
+    fn is_removed_when_a_panic_unwinds() {
+        let parent = tempfile::tempdir().unwrap();
+        let dir = std::panic::catch_unwind(|| {
+            let workspace = Workspace::create_in(parent.path()).unwrap();
+            fs::write(workspace.file("install.ini"), b"[Install]").unwrap();
+            panic!("{}", workspace.path().display());
+        })
+        .unwrap_err();
+
+        let dir = PathBuf::from(dir.downcast_ref::<String>().unwrap());
+        assert!(!dir.exists());
+    }
+}
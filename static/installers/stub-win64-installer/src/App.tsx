@@ -1,15 +1,24 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useTranslation } from "react-i18next";
 
 import { useSlides } from "./app/data/slides";
 import BackgroundDecoration from "./app/layout/BackgroundDecoration";
 import NavBar from "./app/layout/NavBar";
-import InstallerWelcome from "./components/InstallerWelcome";
+import InstallerWelcome, {
+  type ReleaseChannel,
+} from "./components/InstallerWelcome";
 import Slideshow from "./components/Slideshow";
-import ProgressBar from "./components/ProgressBar";
+import ProgressBar, { type DownloadProgress } from "./components/ProgressBar";
 import InstallComplete from "./components/InstallComplete";
 
+type CancelOutcome =
+  | "cancelled"
+  | "confirmationRequired"
+  | "notCancellable"
+  | "idle";
+
 function App() {
   const { t } = useTranslation();
   const slides = useSlides();
@@ -20,23 +29,28 @@ function App() {
   const [currentSlide, setCurrentSlide] = useState(0);
   const [cpuCheckComplete, setCpuCheckComplete] = useState(false);
   const [cpuSupported, setCpuSupported] = useState(true);
+  const [progress, setProgress] = useState<DownloadProgress | null>(null);
+  const cancelModalRef = useRef<HTMLDialogElement>(null);
 
   useEffect(() => {
     // Check CPU support on app startup
     const checkCpuSupport = async () => {
       try {
-        const supported = await invoke<boolean>("check_cpu_support");
+        const missing = await invoke<string[]>("check_cpu_support");
+        const supported = missing.length === 0;
         setCpuSupported(supported);
         setCpuCheckComplete(true);
 
         if (!supported) {
-          setError("rust.errors.cpu_not_supported");
+          setError(
+            t("rust.errors.cpu_features_missing", { 0: missing.join(", ") }),
+          );
         }
       } catch (e) {
         console.error("Failed to check CPU support:", e);
         setCpuSupported(false);
         setCpuCheckComplete(true);
-        setError("rust.errors.cpu_check_failed");
+        setError(t("rust.errors.cpu_check_failed"));
       }
     };
 
@@ -57,6 +71,16 @@ function App() {
     };
   }, [installing, completed, slides.length]);
 
+  useEffect(() => {
+    const unlisten = listen<DownloadProgress>("download-progress", (event) => {
+      setProgress(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   useEffect(() => {
     const handleContextMenu = (event: MouseEvent) => {
       event.preventDefault();
@@ -72,16 +96,21 @@ function App() {
   const handleInstall = async (
     useAdmin: boolean,
     customInstallPath: string | null,
+    channel: ReleaseChannel,
+    maxBytesPerSecond: number | null,
   ) => {
     try {
       setStatus(t("app.status.installing"));
       setError("");
       setInstalling(true);
       setCompleted(false);
+      setProgress(null);
 
       const result = await invoke<string>("download_and_run_installer", {
         useAdmin,
         customInstallPath,
+        channel,
+        maxBytesPerSecond,
       });
 
       setStatus(t(result));
@@ -90,8 +119,8 @@ function App() {
       const errorMessage = e as string;
 
       if (errorMessage.includes("|")) {
-        const [key, param] = errorMessage.split("|");
-        setError(t(key, { 0: param }));
+        const [key, ...params] = errorMessage.split("|");
+        setError(t(key, { ...params }));
       } else {
         setError(t(errorMessage));
       }
@@ -101,11 +130,28 @@ function App() {
     }
   };
 
+  const handleCancel = async (confirmed: boolean) => {
+    cancelModalRef.current?.close();
+    try {
+      const outcome = await invoke<CancelOutcome>("cancel_install", {
+        confirmed,
+      });
+      if (outcome === "confirmationRequired") {
+        cancelModalRef.current?.showModal();
+      } else if (outcome === "notCancellable") {
+        setStatus(t("app.progress.cannotCancel"));
+      }
+    } catch (e) {
+      console.error("Failed to cancel installation:", e);
+    }
+  };
+
   const resetInstaller = () => {
     setInstalling(false);
     setCompleted(false);
     setStatus("");
     setError("");
+    setProgress(null);
     setCurrentSlide(0);
   };
 
@@ -164,7 +210,7 @@ function App() {
               サポートされていないデバイス
             </h2>
             <p className="text-sm mb-6">
-              FloorpはSSE4.1をサポートするプロセッサーが必要です。<br />
+              {error}<br />
               このデバイスはサポート対象外です。
             </p>
             <button
@@ -215,11 +261,33 @@ function App() {
           <ProgressBar
             status={status}
             error={error}
+            progress={progress}
             currentSlide={currentSlide}
             totalSlides={slides.length}
+            onCancel={() => handleCancel(false)}
           />
         )}
       </main>
+
+      <dialog ref={cancelModalRef} className="modal modal-bottom sm:modal-middle">
+        <div className="modal-box">
+          <h3 className="font-bold text-lg">
+            {t("app.cancelConfirmation.title")}
+          </h3>
+          <p className="py-4">{t("app.cancelConfirmation.message")}</p>
+          <div className="modal-action">
+            <button
+              className="btn"
+              onClick={() => cancelModalRef.current?.close()}
+            >
+              {t("app.cancelConfirmation.cancelButton")}
+            </button>
+            <button className="btn btn-error" onClick={() => handleCancel(true)}>
+              {t("app.cancelConfirmation.confirmButton")}
+            </button>
+          </div>
+        </div>
+      </dialog>
     </div>
   );
 }
@@ -2,6 +2,8 @@ import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useTranslation } from "react-i18next";
 
+import SignatureDetails from "./SignatureDetails";
+
 interface InstallCompleteProps {
     success: boolean;
     message: string;
@@ -140,6 +142,8 @@ export default function InstallComplete(
                             </h2>
                             <p className="mb-6 text-error">{message}</p>
 
+                            <SignatureDetails />
+
                             {showLegacyDownload && (
                                 <div className="mb-4 p-3 border-info border-opacity-25">
                                     <p className="text-xs mb-2 text-info">
@@ -1,29 +1,63 @@
 import type React from "react";
-import { useRef, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
+import { invoke } from "@tauri-apps/api/core";
 import installer_png from "../assets/installer.png";
 import { open } from "@tauri-apps/plugin-dialog";
 
+export type ReleaseChannel = "stable" | "beta" | { tag: string };
+
+interface ReleaseSummary {
+    tag: string;
+    name: string;
+    prerelease: boolean;
+    publishedAt: string | null;
+}
+
 interface InstallerWelcomeProps {
-    onInstall: (useAdmin: boolean, customInstallPath: string | null) => void;
+    onInstall: (
+        useAdmin: boolean,
+        customInstallPath: string | null,
+        channel: ReleaseChannel,
+        maxBytesPerSecond: number | null,
+    ) => void;
 }
 
+/** Download rate caps offered in the UI, in bytes per second. */
+const RATE_LIMITS = [256 * 1024, 1024 * 1024, 5 * 1024 * 1024, 10 * 1024 * 1024];
+
+const toChannel = (value: string): ReleaseChannel =>
+    value === "stable" || value === "beta" ? value : { tag: value };
+
 const InstallerWelcome: React.FC<InstallerWelcomeProps> = ({ onInstall }) => {
     const { t } = useTranslation();
     const [useAdmin, setUseAdmin] = useState(true);
     const [customPath, setCustomPath] = useState("");
     const [useCustomPath, setUseCustomPath] = useState(false);
+    const [version, setVersion] = useState("stable");
+    const [releases, setReleases] = useState<ReleaseSummary[]>([]);
+    const [rateLimit, setRateLimit] = useState("");
     const modalRef = useRef<HTMLDialogElement>(null);
 
+    useEffect(() => {
+        invoke<ReleaseSummary[]>("list_installer_versions")
+            .then(setReleases)
+            .catch((error) => {
+                console.error("failed to list versions:", error);
+            });
+    }, []);
+
     const defaultInstallPath = useAdmin
         ? "C:\\Program Files\\Ablaze Floorp"
         : "C:\\Users\\<USERNAME>\\AppData\\Local\\Ablaze Floorp";
 
     const handleInstall = () => {
+        const channel = toChannel(version);
+        const maxBytesPerSecond = rateLimit ? Number(rateLimit) : null;
         if (useCustomPath && customPath) {
-            onInstall(useAdmin, customPath);
+            onInstall(useAdmin, customPath, channel, maxBytesPerSecond);
         } else {
-            onInstall(useAdmin, null);
+            onInstall(useAdmin, null, channel, maxBytesPerSecond);
         }
     };
 
@@ -131,6 +165,59 @@ const InstallerWelcome: React.FC<InstallerWelcomeProps> = ({ onInstall }) => {
                         </div>
                     </div>
 
+                    <div className="form-control w-full max-w-xs mb-4 flex flex-row items-center justify-between gap-4">
+                        <label className="label">
+                            <span className="label-text font-bold">
+                                {t("app.installer.version")}
+                            </span>
+                        </label>
+                        <select
+                            className="select select-bordered select-sm"
+                            value={version}
+                            onChange={(e) => setVersion(e.target.value)}
+                        >
+                            <option value="stable">
+                                {t("app.installer.versionStable")}
+                            </option>
+                            <option value="beta">
+                                {t("app.installer.versionBeta")}
+                            </option>
+                            {releases.map((release) => (
+                                <option key={release.tag} value={release.tag}>
+                                    {release.prerelease
+                                        ? `${release.name} (${t("app.installer.prerelease")})`
+                                        : release.name}
+                                </option>
+                            ))}
+                        </select>
+                    </div>
+
+                    <div className="form-control w-full max-w-xs mb-4 flex flex-row items-center justify-between gap-4">
+                        <label className="label">
+                            <span className="label-text font-bold">
+                                {t("app.installer.speedLimit")}
+                            </span>
+                        </label>
+                        <select
+                            className="select select-bordered select-sm"
+                            value={rateLimit}
+                            onChange={(e) => setRateLimit(e.target.value)}
+                        >
+                            <option value="">
+                                {t("app.installer.speedLimitNone")}
+                            </option>
+                            {RATE_LIMITS.map((limit) => (
+                                <option key={limit} value={limit}>
+                                    {t("app.installer.speedLimitValue", {
+                                        speed: limit >= 1024 * 1024
+                                            ? `${limit / (1024 * 1024)} MB`
+                                            : `${limit / 1024} KB`,
+                                    })}
+                                </option>
+                            ))}
+                        </select>
+                    </div>
+
                     <button
                         className="btn btn-primary btn-lg shadow-xl hover:shadow-primary/20 transform hover:-translate-y-1 transition-all duration-300 px-8 text-white font-bold mt-2"
                         onClick={handleInstall}
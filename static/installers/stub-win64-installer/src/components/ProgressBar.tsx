@@ -1,15 +1,36 @@
 import React from 'react';
 import { useTranslation } from 'react-i18next';
 
+export interface DownloadProgress {
+    downloaded: number;
+    total: number | null;
+    bytesPerSecond: number;
+    etaSeconds: number | null;
+}
+
 interface ProgressBarProps {
     status: string;
     error: string;
+    progress: DownloadProgress | null;
     currentSlide: number;
     totalSlides: number;
+    onCancel: () => void;
 }
 
-const ProgressBar: React.FC<ProgressBarProps> = ({ status, error, currentSlide, totalSlides }) => {
+const formatBytes = (bytes: number) => {
+    const units = ['B', 'KB', 'MB', 'GB'];
+    let value = bytes;
+    let unit = 0;
+    while (value >= 1024 && unit < units.length - 1) {
+        value /= 1024;
+        unit++;
+    }
+    return `${value.toFixed(unit === 0 ? 0 : 1)} ${units[unit]}`;
+};
+
+const ProgressBar: React.FC<ProgressBarProps> = ({ status, error, progress, currentSlide, totalSlides, onCancel }) => {
     const { t } = useTranslation();
+    const downloading = progress !== null && progress.total !== null && progress.downloaded < progress.total;
 
     return (
         <div className="mt-auto pt-6">
@@ -18,22 +39,46 @@ const ProgressBar: React.FC<ProgressBarProps> = ({ status, error, currentSlide,
                     <div className="flex items-center gap-3">
                         <div className="loading loading-spinner loading-md text-primary"></div>
                         <div>
-                            <h3 className="font-bold">{t('app.progress.installing')}</h3>
+                            <h3 className="font-bold">
+                                {downloading ? t('app.progress.downloading') : t('app.progress.installing')}
+                            </h3>
                             <p className="text-sm">{status}</p>
                         </div>
                     </div>
 
                     <div className="flex-1 max-w-3xl">
-                        <progress className="progress progress-primary w-full"></progress>
+                        {downloading
+                            ? (
+                                <>
+                                    <progress
+                                        className="progress progress-primary w-full"
+                                        value={progress.downloaded}
+                                        max={progress.total ?? undefined}
+                                    ></progress>
+                                    <p className="text-xs opacity-70 mt-1">
+                                        {t('app.progress.transferred', {
+                                            downloaded: formatBytes(progress.downloaded),
+                                            total: formatBytes(progress.total ?? 0),
+                                            speed: formatBytes(progress.bytesPerSecond),
+                                        })}
+                                        {progress.etaSeconds !== null &&
+                                            ` · ${t('app.progress.remaining', { count: progress.etaSeconds })}`}
+                                    </p>
+                                </>
+                            )
+                            : <progress className="progress progress-primary w-full"></progress>}
                     </div>
 
-                    <div className="text-right">
+                    <div className="flex items-center justify-end gap-3">
                         <span className="text-sm opacity-70">
                             {t('app.progress.slideCounter', {
                                 current: currentSlide + 1,
                                 total: totalSlides
                             })}
                         </span>
+                        <button className="btn btn-ghost btn-sm" onClick={onCancel}>
+                            {t('app.progress.cancel')}
+                        </button>
                     </div>
                 </div>
 
@@ -47,4 +92,4 @@ const ProgressBar: React.FC<ProgressBarProps> = ({ status, error, currentSlide,
     );
 };
 
-export default ProgressBar; 
\ No newline at end of file
+export default ProgressBar;
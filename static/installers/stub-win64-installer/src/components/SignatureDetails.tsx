@@ -0,0 +1,103 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useTranslation } from "react-i18next";
+
+type Rule =
+    | "signature"
+    | "timestamp"
+    | "validity"
+    | "windows"
+    | "revocation"
+    | "pinning";
+
+export interface SignatureReport {
+    windowsStatus: string | null;
+    subject: string | null;
+    issuer: string | null;
+    serial: string | null;
+    thumbprint: string | null;
+    timestamp: string | null;
+    timestampAuthority: string | null;
+    pin: string | null;
+    revocationUnknown: string | null;
+    failure: { rule: Rule; reason: string } | null;
+}
+
+const FIELDS = [
+    "windowsStatus",
+    "subject",
+    "issuer",
+    "serial",
+    "thumbprint",
+    "timestamp",
+    "timestampAuthority",
+    "pin",
+    "revocationUnknown",
+] as const;
+
+// What the signature check of the failed install found, for the user to
+// pass on to support. Shown only when the installer was rejected.
+export default function SignatureDetails() {
+    const { t } = useTranslation();
+    const [report, setReport] = useState<SignatureReport | null>(null);
+    const [copied, setCopied] = useState(false);
+
+    useEffect(() => {
+        invoke<SignatureReport | null>("get_signature_report")
+            .then(setReport)
+            .catch((e) => {
+                console.error("Failed to get the signature report:", e);
+            });
+    }, []);
+
+    if (!report?.failure) return null;
+
+    const handleCopy = async () => {
+        try {
+            await navigator.clipboard.writeText(
+                JSON.stringify(report, null, 2),
+            );
+            setCopied(true);
+        } catch (e) {
+            console.error("Failed to copy the signature report:", e);
+        }
+    };
+
+    return (
+        <details className="collapse collapse-arrow bg-base-100 mb-4 text-left">
+            <summary className="collapse-title text-sm font-medium">
+                {t("app.complete.signatureDetails.title")}
+            </summary>
+            <div className="collapse-content text-xs">
+                <p className="mb-2 text-error">
+                    {t("app.complete.signatureDetails.failedRule", {
+                        rule: t(
+                            `app.complete.signatureDetails.rules.${report.failure.rule}`,
+                        ),
+                        reason: report.failure.reason,
+                    })}
+                </p>
+                <dl className="grid grid-cols-[auto_1fr] gap-x-3 gap-y-1">
+                    {FIELDS.filter((field) => report[field]).map((field) => (
+                        <div key={field} className="contents">
+                            <dt className="opacity-70">
+                                {t(`app.complete.signatureDetails.${field}`)}
+                            </dt>
+                            <dd className="font-mono break-all">
+                                {report[field]}
+                            </dd>
+                        </div>
+                    ))}
+                </dl>
+                <button
+                    className="btn btn-ghost btn-xs mt-2"
+                    onClick={handleCopy}
+                >
+                    {copied
+                        ? t("app.complete.signatureDetails.copied")
+                        : t("app.complete.signatureDetails.copyButton")}
+                </button>
+            </div>
+        </details>
+    );
+}